@@ -0,0 +1,236 @@
+use super::super::{Metric, TileNet};
+
+/// Stand-in for an infinite squared distance that survives arithmetic
+const FAR: f64 = 1e20;
+
+impl<T> TileNet<T> {
+	/// Compute the distance from every tile to the nearest solid tile
+	///
+	/// Distances are measured between tile centers, so solid tiles are at distance 0.
+	/// If there are no solid tiles at all, every distance is infinite.
+	/// All metrics are exact.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<usize> = TileNet::new(5, 5);
+	/// net.set(&1, (0, 0));
+	/// let field = net.distance_field(Metric::Euclidean, |x| *x == 1);
+	/// assert_eq!(field.get((0, 0)), Some(&0.0));
+	/// assert_eq!(field.get((3, 4)), Some(&5.0));
+	/// ```
+	pub fn distance_field<F>(&self, metric: Metric, solid: F) -> TileNet<f32>
+		where F: FnMut(&T) -> bool
+	{
+		let (cols, rows) = self.get_size();
+		let distances: Vec<f32> = match metric {
+			Metric::Euclidean => {
+				squared_euclidean(&self.solids(solid), cols, rows)
+					.into_iter()
+					.map(|x| if x >= FAR { f32::INFINITY } else { x.sqrt() as f32 })
+					.collect()
+			}
+			_ => {
+				chamfer(&self.solids(solid), cols, rows, metric)
+					.into_iter()
+					.map(|x| if x == u32::MAX { f32::INFINITY } else { x as f32 })
+					.collect()
+			}
+		};
+		TileNet::from_iter(cols.max(1), distances.into_iter())
+	}
+
+	/// Compute the integer distance from every tile to the nearest solid tile
+	///
+	/// Works like `distance_field`, but unreachable distances are `u32::MAX`.
+	/// Since Euclidean distances are rarely integers, `Metric::Euclidean`
+	/// gives the exact squared distance instead.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<usize> = TileNet::new(5, 5);
+	/// net.set(&1, (0, 0));
+	/// let field = net.distance_field_u32(Metric::Chebyshev, |x| *x == 1);
+	/// assert_eq!(field.get((3, 4)), Some(&4));
+	/// let field = net.distance_field_u32(Metric::Euclidean, |x| *x == 1);
+	/// assert_eq!(field.get((3, 4)), Some(&25));
+	/// ```
+	pub fn distance_field_u32<F>(&self, metric: Metric, solid: F) -> TileNet<u32>
+		where F: FnMut(&T) -> bool
+	{
+		let (cols, rows) = self.get_size();
+		let distances = match metric {
+			Metric::Euclidean => {
+				squared_euclidean(&self.solids(solid), cols, rows)
+					.into_iter()
+					.map(|x| if x >= u32::MAX as f64 { u32::MAX } else { x as u32 })
+					.collect()
+			}
+			_ => chamfer(&self.solids(solid), cols, rows, metric),
+		};
+		TileNet::from_iter(cols.max(1), distances.into_iter())
+	}
+
+	/// Compute the signed distance from every tile to the border of the solid regions
+	///
+	/// Non-solid tiles get the distance to the nearest solid tile, and solid tiles get
+	/// the negated distance to the nearest non-solid tile.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<usize> = TileNet::new(7, 1);
+	/// net.set_box(&1, (0, 0), (2, 0));
+	/// let field = net.signed_distance_field(Metric::Manhattan, |x| *x == 1);
+	/// let row: Vec<f32> = field.get_raw().to_vec();
+	/// assert_eq!(row, vec![-3.0, -2.0, -1.0, 1.0, 2.0, 3.0, 4.0]);
+	/// ```
+	pub fn signed_distance_field<F>(&self, metric: Metric, mut solid: F) -> TileNet<f32>
+		where F: FnMut(&T) -> bool
+	{
+		let solids = self.solids(&mut solid);
+		let outside = self.distance_field(metric, &mut solid);
+		let inside = self.distance_field(metric, |x| !solid(x));
+		let (cols, _) = self.get_size();
+		let distances = solids.iter()
+			.zip(outside.get_raw().iter().zip(inside.get_raw().iter()))
+			.map(|(solid, (outside, inside))| if *solid { -*inside } else { *outside });
+		TileNet::from_iter(cols.max(1), distances)
+	}
+
+	fn solids<F>(&self, mut solid: F) -> Vec<bool>
+		where F: FnMut(&T) -> bool
+	{
+		self.get_raw().iter().map(&mut solid).collect()
+	}
+}
+
+/// Exact squared Euclidean distance transform by Felzenszwalb and Huttenlocher
+///
+/// Runs the one-dimensional lower envelope transform over the columns and then the rows.
+fn squared_euclidean(solids: &[bool], cols: usize, rows: usize) -> Vec<f64> {
+	let mut grid: Vec<f64> = solids.iter().map(|x| if *x { 0.0 } else { FAR }).collect();
+	let longest = cols.max(rows);
+	let mut line = vec![0.0; longest];
+	let mut output = vec![0.0; longest];
+	let mut hull = vec![0; longest];
+	let mut bounds = vec![0.0; longest + 1];
+
+	for x in 0..cols {
+		for y in 0..rows {
+			line[y] = grid[x + y * cols];
+		}
+		envelope(&line[..rows], &mut output[..rows], &mut hull, &mut bounds);
+		for y in 0..rows {
+			grid[x + y * cols] = output[y];
+		}
+	}
+	for y in 0..rows {
+		let row = &mut grid[y * cols..(y + 1) * cols];
+		line[..cols].copy_from_slice(row);
+		envelope(&line[..cols], &mut output[..cols], &mut hull, &mut bounds);
+		row.copy_from_slice(&output[..cols]);
+	}
+	grid
+}
+
+/// Lower envelope of the parabolas rooted at every sample of `line`
+fn envelope(line: &[f64], output: &mut [f64], hull: &mut [usize], bounds: &mut [f64]) {
+	if line.is_empty() {
+		return;
+	}
+	let intersect = |q: usize, v: usize| {
+		let (fq, fv) = (q as f64, v as f64);
+		((line[q] + fq * fq) - (line[v] + fv * fv)) / (2.0 * fq - 2.0 * fv)
+	};
+	let mut k = 0;
+	hull[0] = 0;
+	bounds[0] = -f64::INFINITY;
+	bounds[1] = f64::INFINITY;
+	for q in 1..line.len() {
+		let mut s = intersect(q, hull[k]);
+		while s <= bounds[k] {
+			k -= 1;
+			s = intersect(q, hull[k]);
+		}
+		k += 1;
+		hull[k] = q;
+		bounds[k] = s;
+		bounds[k + 1] = f64::INFINITY;
+	}
+	k = 0;
+	for (q, out) in output.iter_mut().enumerate() {
+		while bounds[k + 1] < q as f64 {
+			k += 1;
+		}
+		let offset = q as f64 - hull[k] as f64;
+		*out = (offset * offset + line[hull[k]]).min(FAR);
+	}
+}
+
+/// Exact Manhattan or Chebyshev distance transform using a forward and a backward sweep
+fn chamfer(solids: &[bool], cols: usize, rows: usize, metric: Metric) -> Vec<u32> {
+	let mut grid: Vec<u32> = solids.iter().map(|x| if *x { 0 } else { u32::MAX }).collect();
+	let diagonal = metric == Metric::Chebyshev;
+	let forward: &[(i64, i64)] = if diagonal {
+		&[(-1, 0), (0, -1), (-1, -1), (1, -1)]
+	} else {
+		&[(-1, 0), (0, -1)]
+	};
+	let relax = |grid: &mut Vec<u32>, x: usize, y: usize, sign: i64| {
+		let mut best = grid[x + y * cols];
+		for &(dx, dy) in forward {
+			let (nx, ny) = (x as i64 + dx * sign, y as i64 + dy * sign);
+			if nx >= 0 && ny >= 0 && (nx as usize) < cols && (ny as usize) < rows {
+				best = best.min(grid[nx as usize + ny as usize * cols].saturating_add(1));
+			}
+		}
+		grid[x + y * cols] = best;
+	};
+	for y in 0..rows {
+		for x in 0..cols {
+			relax(&mut grid, x, y, 1);
+		}
+	}
+	for y in (0..rows).rev() {
+		for x in (0..cols).rev() {
+			relax(&mut grid, x, y, -1);
+		}
+	}
+	grid
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn matches_brute_force() {
+		let mut seed = 99u32;
+		let net: TileNet<bool> = TileNet::from_iter(17,
+		                                            (0..17 * 11).map(|_| {
+			seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+			(seed >> 16) % 23 == 1
+		}));
+		let solids: Vec<(usize, usize)> = net.view_all()
+			.filter(|x| *x.0)
+			.map(|x| (x.1, x.2))
+			.collect();
+		for &metric in &[Metric::Manhattan, Metric::Chebyshev, Metric::Euclidean] {
+			let field = net.distance_field(metric, |x| *x);
+			for (distance, col, row) in field.view_all() {
+				let expected = solids.iter()
+					.map(|s| metric.distance(s.0 as f32 - col as f32, s.1 as f32 - row as f32))
+					.fold(f32::INFINITY, f32::min);
+				assert!((distance - expected).abs() < 1e-4);
+			}
+		}
+	}
+
+	#[test]
+	fn no_solids() {
+		let net: TileNet<usize> = TileNet::new(4, 3);
+		let field = net.distance_field(Metric::Euclidean, |x| *x == 1);
+		assert!(field.get_raw().iter().all(|x| x.is_infinite()));
+		let field = net.distance_field_u32(Metric::Manhattan, |x| *x == 1);
+		assert!(field.get_raw().iter().all(|x| *x == u32::MAX));
+	}
+}
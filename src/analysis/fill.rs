@@ -0,0 +1,100 @@
+use super::super::{Connectivity, Span, TileNet, TileNetProxy};
+
+impl<T> TileNet<T> {
+	/// Find the region of matching tiles connected to a start tile
+	///
+	/// Returns every tile for which the predicate holds and that can be reached from
+	/// `start` through matching tiles. The region is empty if `start` does not match.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<usize> = TileNet::new(10, 10);
+	/// net.set_box(&1, (2, 2), (5, 5));
+	/// net.set_box(&0, (3, 3), (4, 4));
+	/// let room = net.flood_fill((3, 3), Connectivity::Four, |x| *x == 0);
+	/// assert_eq!(room.len(), 4);
+	/// ```
+	pub fn flood_fill<F>(&self,
+	                     start: (usize, usize),
+	                     connectivity: Connectivity,
+	                     mut predicate: F)
+	                     -> Vec<(usize, usize)>
+		where F: FnMut(&T) -> bool
+	{
+		let mut region = vec![];
+		match self.get(start) {
+			Some(tile) if predicate(tile) => region.push(start),
+			_ => return region,
+		}
+		let cols = self.get_size().0;
+		let mut visited = vec![false; self.get_raw().len()];
+		visited[start.0 + start.1 * cols] = true;
+		let mut index = 0;
+		while index < region.len() {
+			let current = region[index];
+			index += 1;
+			for &offset in connectivity.offsets() {
+				if let Some(next) = self.offset(current, offset) {
+					let flat = next.0 + next.1 * cols;
+					if !visited[flat] {
+						visited[flat] = true;
+						if predicate(self.get(next).unwrap()) {
+							region.push(next);
+						}
+					}
+				}
+			}
+		}
+		region
+	}
+}
+
+impl<'a, T> TileNetProxy<'a, T>
+    where T: Clone
+{
+	/// Paint-bucket fill the region of matching tiles connected to a start tile
+	///
+	/// The region is found as in `TileNet::flood_fill` before any tile is written,
+	/// so the predicate only ever sees the original tiles.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<usize> = TileNet::new(10, 10);
+	/// net.set_box(&1, (2, 2), (5, 5));
+	/// net.set_box(&0, (3, 3), (4, 4));
+	/// let mut proxy = net.prepare();
+	/// let span = proxy.flood_fill((3, 3), Connectivity::Four, |x| *x == 0, &2);
+	/// assert_eq!(span, (3, 3, 4, 4));
+	/// ```
+	pub fn flood_fill<F>(&mut self,
+	                     start: (usize, usize),
+	                     connectivity: Connectivity,
+	                     predicate: F,
+	                     value: &T)
+	                     -> Span
+		where F: FnMut(&T) -> bool
+	{
+		let region = self.get_tilenet().flood_fill(start, connectivity, predicate);
+		for tile in region {
+			self.set(value, tile);
+		}
+		self.get_span()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn diagonal_leak() {
+		let mut net: TileNet<usize> = TileNet::new(4, 4);
+		net.set(&1, (1, 0));
+		net.set(&1, (0, 1));
+		let four = net.flood_fill((0, 0), Connectivity::Four, |x| *x == 0);
+		assert_eq!(four, vec![(0, 0)]);
+		let eight = net.flood_fill((0, 0), Connectivity::Eight, |x| *x == 0);
+		assert_eq!(eight.len(), 14);
+		assert!(net.flood_fill((1, 0), Connectivity::Four, |x| *x == 0).is_empty());
+	}
+}
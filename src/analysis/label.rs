@@ -0,0 +1,116 @@
+use super::super::{Connectivity, Span, TileNet};
+
+/// Result of labeling the connected components of a `TileNet`
+#[derive(Clone, Debug)]
+pub struct Labels {
+	/// The label of every tile, where 0 marks tiles that did not match
+	pub grid: TileNet<usize>,
+	/// The components, where the component with label `n` is at index `n - 1`
+	pub components: Vec<Component>,
+}
+
+/// Statistics of a single connected component
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Component {
+	/// The label of the component in the label grid
+	pub label: usize,
+	/// The number of tiles in the component
+	pub area: usize,
+	/// The bounding box as (min x, min y, max x, max y), all inclusive
+	pub bounds: Span,
+	/// Whether the component touches the border of the net
+	///
+	/// A component that does not touch the border is enclosed, like a room or a pocket.
+	pub touches_border: bool,
+}
+
+impl<T> TileNet<T> {
+	/// Label the connected components of matching tiles
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<usize> = TileNet::new(10, 10);
+	/// net.set_box(&1, (2, 2), (6, 6));
+	/// net.set_box(&0, (3, 3), (5, 5));
+	/// let labels = net.label(Connectivity::Four, |x| *x == 0);
+	/// assert_eq!(labels.components.len(), 2);
+	/// let room = labels.components.iter().find(|x| !x.touches_border).unwrap();
+	/// assert_eq!(room.area, 9);
+	/// assert_eq!(room.bounds, (3, 3, 5, 5));
+	/// assert_eq!(labels.grid.get((4, 4)), Some(&room.label));
+	/// ```
+	pub fn label<F>(&self, connectivity: Connectivity, mut predicate: F) -> Labels
+		where F: FnMut(&T) -> bool
+	{
+		let (cols, rows) = self.get_size();
+		let matching: Vec<bool> = self.get_raw().iter().map(&mut predicate).collect();
+		let mut grid = vec![0; matching.len()];
+		let mut components = vec![];
+		let mut queue = vec![];
+
+		for start in 0..matching.len() {
+			if !matching[start] || grid[start] != 0 {
+				continue;
+			}
+			let label = components.len() + 1;
+			let mut component = Component {
+				label,
+				area: 0,
+				bounds: (start % cols, start / cols, start % cols, start / cols),
+				touches_border: false,
+			};
+			grid[start] = label;
+			queue.clear();
+			queue.push(start);
+			while let Some(current) = queue.pop() {
+				let (x, y) = (current % cols, current / cols);
+				component.area += 1;
+				component.bounds.0 = component.bounds.0.min(x);
+				component.bounds.1 = component.bounds.1.min(y);
+				component.bounds.2 = component.bounds.2.max(x);
+				component.bounds.3 = component.bounds.3.max(y);
+				if x == 0 || y == 0 || x + 1 == cols || y + 1 == rows {
+					component.touches_border = true;
+				}
+				for &offset in connectivity.offsets() {
+					if let Some(next) = self.offset((x, y), offset) {
+						let next = next.0 + next.1 * cols;
+						if matching[next] && grid[next] == 0 {
+							grid[next] = label;
+							queue.push(next);
+						}
+					}
+				}
+			}
+			components.push(component);
+		}
+
+		Labels {
+			grid: TileNet::from_iter(cols.max(1), grid.into_iter()),
+			components,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn islands() {
+		let net: TileNet<usize> = TileNet::from_iter(5,
+		                                             vec![1, 0, 0, 0, 1,
+		                                                  0, 1, 0, 0, 0,
+		                                                  0, 0, 0, 1, 1,
+		                                                  0, 0, 0, 1, 0]
+			                                             .into_iter());
+		let four = net.label(Connectivity::Four, |x| *x == 1);
+		assert_eq!(four.components.len(), 4);
+		let eight = net.label(Connectivity::Eight, |x| *x == 1);
+		assert_eq!(eight.components.len(), 3);
+		assert_eq!(eight.components[0].area, 2);
+		assert_eq!(eight.components[2].bounds, (3, 2, 4, 3));
+		assert_eq!(eight.grid.get((3, 3)), Some(&3));
+		assert_eq!(eight.grid.get((2, 2)), Some(&0));
+	}
+}
@@ -0,0 +1,291 @@
+use std::collections::VecDeque;
+use super::super::{Connectivity, Span, TileNet};
+
+/// How a tile interacts with light
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Lighting {
+	/// The light level the tile emits by itself
+	pub emission: u8,
+	/// How much light is lost when light enters the tile
+	///
+	/// Light always loses at least one level per tile, so 0 and 1 behave the
+	/// same. A value of 255 makes the tile opaque.
+	pub absorption: u8,
+}
+
+/// Light levels flood-filled from emitting tiles
+///
+/// Light spreads to the four orthogonal neighbours, losing the absorption of
+/// the tile it enters. When tiles change, only the light that passed through
+/// them is removed and filled in again.
+///
+/// ```
+/// use tile_net::*;
+/// let mut net: TileNet<usize> = TileNet::new(9, 1);
+/// net.set(&1, (0, 0));
+/// net.set(&2, (4, 0));
+/// let lighting = |x: &usize| match *x {
+///   1 => Lighting { emission: 10, absorption: 0 },
+///   2 => Lighting { emission: 0, absorption: 255 },
+///   _ => Lighting::default(),
+/// };
+/// let mut light = LightMap::new(&net, lighting);
+/// assert_eq!(light.lights().get_raw(), &[10, 9, 8, 7, 0, 0, 0, 0, 0]);
+/// // Dig out the wall
+/// let span = {
+///   let mut proxy = net.prepare();
+///   proxy.set(&0, (4, 0));
+///   proxy.get_span()
+/// };
+/// light.update(&net, span, lighting);
+/// assert_eq!(light.lights().get_raw(), &[10, 9, 8, 7, 6, 5, 4, 3, 2]);
+/// ```
+pub struct LightMap {
+	light: TileNet<u8>,
+	lighting: TileNet<Lighting>,
+	removal: VecDeque<((usize, usize), u8)>,
+	addition: VecDeque<(usize, usize)>,
+}
+
+impl LightMap {
+	/// Compute the light of a net
+	pub fn new<T, F>(net: &TileNet<T>, lighting: F) -> LightMap
+		where F: FnMut(&T) -> Lighting
+	{
+		let mut map = LightMap {
+			light: TileNet::new(0, 0),
+			lighting: TileNet::new(0, 0),
+			removal: VecDeque::new(),
+			addition: VecDeque::new(),
+		};
+		map.rebuild(net, lighting);
+		map
+	}
+
+	/// Compute all light again
+	pub fn rebuild<T, F>(&mut self, net: &TileNet<T>, lighting: F)
+		where F: FnMut(&T) -> Lighting
+	{
+		let (cols, rows) = net.get_size();
+		self.lighting = TileNet::from_iter(cols.max(1), net.get_raw().iter().map(lighting));
+		self.light = TileNet::from_iter(cols.max(1),
+		                                self.lighting.get_raw().iter().map(|x| x.emission));
+		self.addition.clear();
+		for y in 0..rows {
+			for x in 0..cols {
+				if *self.light.get((x, y)).unwrap() > 0 {
+					self.addition.push_back((x, y));
+				}
+			}
+		}
+		self.spread();
+	}
+
+	/// Update the light after the tiles within a span have changed
+	///
+	/// Light that reached any tile through the span is removed and then filled in
+	/// again from the surrounding light and from the emitters in the removed area.
+	/// If the net has been resized, all light is computed again.
+	pub fn update<T, F>(&mut self, net: &TileNet<T>, span: Span, mut lighting: F)
+		where F: FnMut(&T) -> Lighting
+	{
+		if net.get_size() != self.light.get_size() {
+			return self.rebuild(net, lighting);
+		}
+		let (left, top, right, bottom) = match net.clamp_span(span, 0) {
+			Some(span) => span,
+			None => return,
+		};
+		self.removal.clear();
+		self.addition.clear();
+		for y in top..bottom + 1 {
+			for x in left..right + 1 {
+				self.lighting.set(&lighting(net.get((x, y)).unwrap()), (x, y));
+				let level = *self.light.get((x, y)).unwrap();
+				self.light.set(&0, (x, y));
+				self.removal.push_back(((x, y), level));
+			}
+		}
+
+		let mut emitters = vec![];
+		while let Some((current, level)) = self.removal.pop_front() {
+			if self.lighting.get(current).unwrap().emission > 0 {
+				emitters.push(current);
+			}
+			for &offset in Connectivity::Four.offsets() {
+				let next = match self.light.offset(current, offset) {
+					Some(next) => next,
+					None => continue,
+				};
+				let light = *self.light.get(next).unwrap();
+				if light == 0 {
+					continue;
+				}
+				if light < level {
+					self.light.set(&0, next);
+					self.removal.push_back((next, light));
+				} else {
+					self.addition.push_back(next);
+				}
+			}
+		}
+
+		for emitter in emitters {
+			let emission = self.lighting.get(emitter).unwrap().emission;
+			if emission > *self.light.get(emitter).unwrap() {
+				self.light.set(&emission, emitter);
+				self.addition.push_back(emitter);
+			}
+		}
+		// Light may now pass through tiles that absorb less than before
+		for y in top..bottom + 1 {
+			for x in left..right + 1 {
+				for &offset in Connectivity::Four.offsets() {
+					if let Some(next) = self.light.offset((x, y), offset) {
+						self.addition.push_back(next);
+					}
+				}
+			}
+		}
+		self.spread();
+	}
+
+	/// Get the light level of a tile
+	pub fn light(&self, p: (usize, usize)) -> Option<u8> {
+		self.light.get(p).cloned()
+	}
+
+	/// Get the whole light map
+	pub fn lights(&self) -> &TileNet<u8> {
+		&self.light
+	}
+
+	/// Flood light outward from the tiles in the addition queue
+	fn spread(&mut self) {
+		while let Some(current) = self.addition.pop_front() {
+			let level = *self.light.get(current).unwrap();
+			if level <= 1 {
+				continue;
+			}
+			for &offset in Connectivity::Four.offsets() {
+				let next = match self.light.offset(current, offset) {
+					Some(next) => next,
+					None => continue,
+				};
+				let absorption = self.lighting.get(next).unwrap().absorption.max(1);
+				let light = level.saturating_sub(absorption);
+				if light > *self.light.get(next).unwrap() {
+					self.light.set(&light, next);
+					self.addition.push_back(next);
+				}
+			}
+		}
+	}
+}
+
+/// Light with separate red, green, and blue channels
+///
+/// Each channel is a `LightMap` of its own, so a tile may for example let
+/// red light through while blocking blue light.
+pub struct ColorLightMap {
+	channels: [LightMap; 3],
+}
+
+impl ColorLightMap {
+	/// Compute the light of a net, given the lighting of every channel of a tile
+	pub fn new<T, F>(net: &TileNet<T>, mut lighting: F) -> ColorLightMap
+		where F: FnMut(&T) -> [Lighting; 3]
+	{
+		ColorLightMap {
+			channels: [LightMap::new(net, |x| lighting(x)[0]),
+			           LightMap::new(net, |x| lighting(x)[1]),
+			           LightMap::new(net, |x| lighting(x)[2])],
+		}
+	}
+
+	/// Update the light after the tiles within a span have changed
+	pub fn update<T, F>(&mut self, net: &TileNet<T>, span: Span, mut lighting: F)
+		where F: FnMut(&T) -> [Lighting; 3]
+	{
+		for (channel, map) in self.channels.iter_mut().enumerate() {
+			map.update(net, span, |x| lighting(x)[channel]);
+		}
+	}
+
+	/// Get the red, green, and blue light levels of a tile
+	pub fn light(&self, p: (usize, usize)) -> Option<[u8; 3]> {
+		Some([self.channels[0].light(p)?, self.channels[1].light(p)?, self.channels[2].light(p)?])
+	}
+
+	/// Get the light map of a single channel, where 0 is red, 1 is green, and 2 is blue
+	pub fn channel(&self, channel: usize) -> &LightMap {
+		&self.channels[channel]
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn lighting(x: &usize) -> Lighting {
+		match *x {
+			1 => Lighting { emission: 14, absorption: 0 },
+			2 => Lighting { emission: 0, absorption: 255 },
+			3 => Lighting { emission: 0, absorption: 3 },
+			4 => Lighting { emission: 9, absorption: 255 },
+			_ => Lighting::default(),
+		}
+	}
+
+	#[test]
+	fn update_matches_rebuild() {
+		let mut seed = 5u32;
+		let mut random = move || {
+			seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+			(seed >> 16) as usize
+		};
+		let mut net: TileNet<usize> = TileNet::from_iter(32, (0..32 * 24).map(|_| {
+			match random() % 40 {
+				0 => 1,
+				1 => 4,
+				2..=9 => 2,
+				10..=13 => 3,
+				_ => 0,
+			}
+		}));
+		let mut light = LightMap::new(&net, lighting);
+		for _ in 0..60 {
+			let (x, y) = (random() % 30, random() % 22);
+			let value = random() % 5;
+			let span = {
+				let mut proxy = net.prepare();
+				proxy.set_box(&value, (x, y), (x + random() % 3, y + random() % 3));
+				proxy.get_span()
+			};
+			light.update(&net, span, lighting);
+			let fresh = LightMap::new(&net, lighting);
+			assert_eq!(light.lights().get_raw(), fresh.lights().get_raw());
+		}
+	}
+
+	#[test]
+	fn colored_channels() {
+		let mut net: TileNet<usize> = TileNet::new(5, 1);
+		net.set(&1, (0, 0));
+		net.set(&2, (2, 0));
+		// A yellow torch behind glass that only lets green light through
+		let light = ColorLightMap::new(&net, |x| match *x {
+			1 => {
+				let torch = Lighting { emission: 8, absorption: 0 };
+				[torch, torch, Lighting::default()]
+			}
+			2 => {
+				let wall = Lighting { emission: 0, absorption: 255 };
+				[wall, Lighting::default(), wall]
+			}
+			_ => [Lighting::default(); 3],
+		});
+		assert_eq!(light.light((1, 0)), Some([7, 7, 0]));
+		assert_eq!(light.light((3, 0)), Some([0, 5, 0]));
+	}
+}
@@ -0,0 +1,8 @@
+pub use self::label::{Component, Labels};
+pub use self::light::{ColorLightMap, LightMap, Lighting};
+
+mod distance;
+mod fill;
+mod label;
+mod light;
+mod nearest;
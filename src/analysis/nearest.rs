@@ -0,0 +1,118 @@
+use super::super::{Metric, TileNet};
+
+impl<T> TileNet<T> {
+	/// Find the nearest tile that matches a predicate
+	///
+	/// Searches outward from `position` in square rings and stops as soon as no
+	/// closer tile can exist, or when the rings pass `max_radius` or the net. Only
+	/// the parts of the rings within the net are visited. Distances are measured
+	/// from `position` to the centers of the tiles.
+	///
+	/// Returns the coordinate of the tile together with its distance.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<usize> = TileNet::new(10, 10);
+	/// net.set(&1, (7, 2));
+	/// net.set(&1, (1, 8));
+	/// let ladder = net.nearest((2.5, 6.5), 10.0, Metric::Euclidean, |x| *x == 1);
+	/// assert_eq!(ladder, Some(((1, 8), 2.236068)));
+	/// assert_eq!(net.nearest((2.5, 6.5), 2.0, Metric::Euclidean, |x| *x == 1), None);
+	/// ```
+	pub fn nearest<F>(&self,
+	                  position: (f32, f32),
+	                  max_radius: f32,
+	                  metric: Metric,
+	                  mut predicate: F)
+	                  -> Option<((usize, usize), f32)>
+		where F: FnMut(&T) -> bool
+	{
+		let (cols, rows) = (self.get_size().0 as i64, self.get_size().1 as i64);
+		if cols == 0 || rows == 0 {
+			return None;
+		}
+		let base = (position.0.floor() as i64, position.1.floor() as i64);
+		// The rings between these two reach the net
+		let closest = 0.max(-base.0).max(base.0 - cols + 1).max(-base.1).max(base.1 - rows + 1);
+		let furthest = (base.0.abs())
+			.max((base.0 - cols + 1).abs())
+			.max(base.1.abs())
+			.max((base.1 - rows + 1).abs());
+		let mut best: Option<((usize, usize), f32)> = None;
+
+		for radius in closest..furthest + 1 {
+			// Every tile center in this ring is at least this far away on one axis
+			let bound = (radius as f32 - 0.5).max(0.0);
+			if bound > max_radius || best.map(|x| x.1 <= bound).unwrap_or(false) {
+				break;
+			}
+			let mut visit = |x: i64, y: i64| {
+				if x < 0 || y < 0 || x >= cols || y >= rows {
+					return;
+				}
+				let tile = (x as usize, y as usize);
+				let distance = metric.distance(x as f32 + 0.5 - position.0,
+				                               y as f32 + 0.5 - position.1);
+				if distance <= max_radius && best.map(|x| distance < x.1).unwrap_or(true) &&
+				   predicate(self.get(tile).unwrap()) {
+					best = Some((tile, distance));
+				}
+			};
+			if radius == 0 {
+				visit(base.0, base.1);
+				continue;
+			}
+			let (left, right) = ((base.0 - radius).max(0), (base.0 + radius).min(cols - 1));
+			for x in left..right + 1 {
+				visit(x, base.1 - radius);
+				visit(x, base.1 + radius);
+			}
+			let (top, bottom) = ((base.1 - radius + 1).max(0), (base.1 + radius - 1).min(rows - 1));
+			for y in top..bottom + 1 {
+				visit(base.0 - radius, y);
+				visit(base.0 + radius, y);
+			}
+		}
+		best
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn matches_full_scan() {
+		let mut seed = 3u32;
+		let net: TileNet<bool> = TileNet::from_iter(23,
+		                                            (0..23 * 19).map(|_| {
+			seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+			(seed >> 16) % 41 == 1
+		}));
+		for &metric in &[Metric::Manhattan, Metric::Chebyshev, Metric::Euclidean] {
+			let positions =
+				[(0.2, 0.7), (11.5, 9.1), (22.9, 18.9), (-4.0, 30.0), (7.3, 2.2), (60.5, -45.0)];
+			for &position in &positions {
+				let expected = net.view_all()
+					.filter(|x| *x.0)
+					.map(|x| metric.distance(x.1 as f32 + 0.5 - position.0,
+					                         x.2 as f32 + 0.5 - position.1))
+					.fold(f32::INFINITY, f32::min);
+				let found = net.nearest(position, 100.0, metric, |x| *x).unwrap();
+				assert_eq!(found.1, expected);
+				assert!(*net.get(found.0).unwrap());
+			}
+		}
+	}
+
+	#[test]
+	fn far_outside() {
+		let mut net: TileNet<usize> = TileNet::new(10, 10);
+		let far = (5000.5, 5000.5);
+		assert_eq!(net.nearest(far, f32::INFINITY, Metric::Chebyshev, |x| *x == 1), None);
+		net.set(&1, (9, 3));
+		let found = net.nearest(far, f32::INFINITY, Metric::Chebyshev, |x| *x == 1);
+		assert_eq!(found, Some(((9, 3), 4997.0)));
+		assert_eq!(net.nearest(far, 4996.0, Metric::Chebyshev, |x| *x == 1), None);
+	}
+}
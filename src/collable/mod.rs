@@ -1,15 +1,34 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
 pub use super::{SuperCover, Line, Vector, TileNet, TileSet};
 
 pub use interleave::{IterList, MultiIter};
+pub use self::slide::Slide;
+
+mod slide;
 
 /// A vertex iterator.
 ///
 /// Used internally by the collision engine. It combines static
 /// points with an offset. Every iteration returns the point + offset.
+///
+/// When edges are given, the points are followed by extra points along every
+/// edge, spaced at most one tile apart. This keeps objects that are larger than
+/// a tile from passing around a tile that lies between two of their vertices.
 pub struct Points<'a> {
 	index: usize,
 	offset: Vector,
-	points: &'a [(f32, f32)],
+	points: Cow<'a, [(f32, f32)]>,
+	edges: Edges<'a>,
+	edge: usize,
+	step: usize,
+}
+
+/// The edges along which `Points` adds extra points
+enum Edges<'a> {
+	None,
+	Listed(&'a [(usize, usize)]),
+	Closed,
 }
 
 impl<'a> Points<'a> {
@@ -28,10 +47,66 @@ impl<'a> Points<'a> {
 	/// ```
 	///
 	pub fn new(offset: Vector, points: &'a [(f32, f32)]) -> Points {
+		Points::create(offset, Cow::Borrowed(points), Edges::None)
+	}
+
+	/// Create a points iterator that owns its points
+	///
+	/// Useful when the points are computed on the fly, like the sample points of a `Shape`.
+	pub fn owned(offset: Vector, points: Vec<(f32, f32)>) -> Points<'static> {
+		Points::create(offset, Cow::Owned(points), Edges::None)
+	}
+
+	/// Create a points iterator that also samples the edges between points
+	///
+	/// Every edge is a pair of indices into `points`.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let initial = vec![(0.0, 0.0), (3.0, 0.0)];
+	/// let points: Vec<_> = Points::with_edges(Vector(0.0, 1.0), &initial, &[(0, 1)]).collect();
+	/// assert_eq!(points, vec![(0.0, 1.0), (3.0, 1.0), (1.0, 1.0), (2.0, 1.0)]);
+	/// ```
+	pub fn with_edges(offset: Vector,
+	                  points: &'a [(f32, f32)],
+	                  edges: &'a [(usize, usize)])
+	                  -> Points<'a> {
+		Points::create(offset, Cow::Borrowed(points), Edges::Listed(edges))
+	}
+
+	/// Create a points iterator for a closed polygon, sampling the edges between points
+	///
+	/// Each point is connected to the next, and the last point to the first.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let square = vec![(0.0, 0.0), (3.0, 0.0), (3.0, 3.0), (0.0, 3.0)];
+	/// assert_eq!(Points::polygon(Vector(0.0, 0.0), &square).count(), 12);
+	/// ```
+	pub fn polygon(offset: Vector, points: &'a [(f32, f32)]) -> Points<'a> {
+		Points::create(offset, Cow::Borrowed(points), Edges::Closed)
+	}
+
+	fn create(offset: Vector, points: Cow<'a, [(f32, f32)]>, edges: Edges<'a>) -> Points<'a> {
 		Points {
 			index: 0,
-			offset: offset,
-			points: points,
+			offset,
+			points,
+			edges,
+			edge: 0,
+			step: 0,
+		}
+	}
+
+	/// Get the indices of the points at the ends of an edge
+	fn edge(&self, edge: usize) -> Option<(usize, usize)> {
+		let count = self.points.len();
+		match self.edges {
+			Edges::None => None,
+			Edges::Listed(edges) => edges.get(edge).cloned(),
+			Edges::Closed if count > 2 && edge < count => Some((edge, (edge + 1) % count)),
+			Edges::Closed if count == 2 && edge == 0 => Some((0, 1)),
+			Edges::Closed => None,
 		}
 	}
 }
@@ -39,67 +114,250 @@ impl<'a> Points<'a> {
 impl<'a> Iterator for Points<'a> {
 	type Item = (f32, f32);
 	fn next(&mut self) -> Option<Self::Item> {
-		let ret = self.points
-			.get(self.index)
-			.cloned()
-			.map(|x| Vector::from_tuple(x) + self.offset)
-			.map(|x| (x.0, x.1));
-		self.index += 1;
-		ret
+		if self.index < self.points.len() {
+			let ret = Vector::from_tuple(self.points[self.index]) + self.offset;
+			self.index += 1;
+			return Some((ret.0, ret.1));
+		}
+		while let Some((first, second)) = self.edge(self.edge) {
+			let from = Vector::from_tuple(self.points[first]);
+			let mut along = Vector::from_tuple(self.points[second]) - from;
+			let steps = along.norm2sq().sqrt().ceil().max(1.0) as usize;
+			self.step += 1;
+			if self.step < steps {
+				along.scale(self.step as f32 / steps as f32);
+				let ret = from + along + self.offset;
+				return Some((ret.0, ret.1));
+			}
+			self.edge += 1;
+			self.step = 0;
+		}
+		None
+	}
+}
+
+/// Outcome of a `Collable::solve` call
+///
+/// The same report is used by `VoxelCollable`, with a 3D movement and voxel coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolveReport<V = Vector, P = (i32, i32)> {
+	/// The number of times `resolve` was called
+	pub iterations: usize,
+	/// Whether the iteration limit was reached without `resolve` accepting a movement
+	pub exhausted: bool,
+	/// The movement queued in the state after the last iteration
+	pub queued: V,
+	/// The tiles that `resolve` marked with `TileSet::block`
+	///
+	/// Each tile is listed once, in the order it was marked.
+	pub blocking: Vec<P>,
+}
+
+/// Call `attempt` until it accepts a movement or `max_iterations` is reached
+///
+/// `attempt` is given the list that the blocking tiles are recorded in. Returns the
+/// number of iterations, whether a movement was accepted, and the blocking tiles.
+pub(crate) fn resolve_loop<P, F>(max_iterations: usize, mut attempt: F) -> (usize, bool, Vec<P>)
+	where F: FnMut(&RefCell<Vec<P>>) -> bool
+{
+	let blocking = RefCell::new(vec![]);
+	let mut iterations = 0;
+	while iterations < max_iterations {
+		iterations += 1;
+		if attempt(&blocking) {
+			return (iterations, true, blocking.into_inner());
+		}
+	}
+	(iterations, false, blocking.into_inner())
+}
+
+/// State that is passed through a single `Collable::solve` call
+///
+/// The state carries the movement that is tried. Anything else that the
+/// `presolve`, `resolve`, and `postsolve` stages need to share can be put in it as well.
+pub trait CollableState {
+	/// Returns the movement vector that the next iteration should try
+	fn queued(&self) -> Vector;
+
+	/// Returns the rotation in radians that the next iteration should try
+	///
+	/// Positive angles turn from the x axis toward the y axis, which is clockwise
+	/// when y points down. The rotation is applied around `Collable::pivot` before
+	/// the movement. Defaults to no rotation.
+	fn queued_rotation(&self) -> f32 {
+		0.0
 	}
 }
 
 /// Trait for dynamic objects so they can easily check collisions with the `TileMap`
-pub trait Collable<T> {
+pub trait Collable<T, S>
+	where S: CollableState
+{
 	/// Returns the set of points associated with this object. These points are used to
 	/// draw lines to their respective next points. For a rectangle, the four courners
 	/// may be points. For a circle, a whole bunch of points may be defined.
 	fn points(&self) -> Points;
 
-	/// Returns the movement vector of the object
-	fn queued(&self) -> Vector;
-
 	/// Resolve the movement: you get a set of tiles and you decide what to do with them.
-	/// If you aren't satisfied, you can change the move vector and return false, this means
-	/// that we'll try again. Another set of tiles may then be given.
+	/// If you aren't satisfied, you can change the move vector in the state and return false,
+	/// this means that we'll try again. Another set of tiles may then be given.
 	/// If you're satisfied, return true and adjust your `Collable`'s position accordingly.
+	/// Call `TileSet::block` after reading a tile that stops you, so `solve` can report it.
 	///
 	/// IMPORTANT: You should add the move from queued_move to your point set. The ray tracer
 	/// also adds to find the next points. This will prevent you from getting stuck in a wall.
-	fn resolve<I>(&mut self, set: TileSet<T, I>) -> bool
+	fn resolve<I>(&mut self, set: TileSet<T, I>, state: &mut S) -> bool
 		where I: Iterator<Item = (i32, i32)>;
 
 	/// Called at the beginning of `solve`
 	///
 	/// This method is useful when resetting internal variables of state.
 	/// An example of this is when you have to set a has-jumped variable.
-	fn presolve(&mut self) {}
+	fn presolve(&mut self, _state: &mut S) {}
 
 	/// Called at the end of `solve`.
 	///
 	/// Used to process the result from the resolve loop.
-	fn postsolve(&mut self, _collided_once: bool, _resolved: bool) {}
+	fn postsolve(&mut self, _collided_once: bool, _resolved: bool, _state: &mut S) {}
+
+	/// The point that the object rotates around
+	///
+	/// Defaults to the offset of `points`, which is usually the position of the object.
+	fn pivot(&self) -> Vector {
+		self.points().offset
+	}
+
+	/// The number of iterations `solve` may use
+	///
+	/// Override this for objects whose resolver needs more or fewer attempts.
+	fn max_iterations(&self) -> usize {
+		30
+	}
 
 	/// Convenience function for the resolve loop
 	///
 	/// Calls presolve at the beginning and postsolve at the end.
-	/// Runs the resolve function in a loop of at max 30 iterations.
-	/// This is to avoid potential deadlock if the resolve function
+	/// Runs the resolve function in a loop of at most `max_iterations` iterations.
+	/// Every iteration traces the movement given by `CollableState::queued` and the
+	/// rotation given by `CollableState::queued_rotation`. A resolver that accepts a
+	/// rotation should apply it to the object as well.
+	/// The limit is to avoid potential deadlock if the resolve function
 	/// is poorly coded and returns false all the time.
-	fn solve(&mut self, net: &TileNet<T>) {
-		self.presolve();
-		static MAX_ITERATIONS: usize = 30;
-		let mut collided_once = false;
-		let mut resolved = false;
-		for _ in 0..MAX_ITERATIONS {
-			let tiles = net.collide_set(self.tiles(self.queued()));
-			if self.resolve(tiles) {
-				resolved = true;
+	fn solve(&mut self, net: &TileNet<T>, state: &mut S) -> SolveReport {
+		let max_iterations = self.max_iterations();
+		self.solve_with(net, state, max_iterations)
+	}
+
+	/// Same as `solve`, but with the iteration limit given for this call only
+	fn solve_with(&mut self,
+	              net: &TileNet<T>,
+	              state: &mut S,
+	              max_iterations: usize)
+	              -> SolveReport {
+		self.presolve(state);
+		let (iterations, resolved, blocking) = resolve_loop(max_iterations, |blocking| {
+			let points = self.tiles_rotated(state.queued(), state.queued_rotation());
+			self.resolve(net.collide_set(points).recording(blocking), state)
+		});
+		let collided_once = iterations > resolved as usize;
+		self.postsolve(collided_once, resolved, state);
+		SolveReport {
+			iterations,
+			exhausted: !resolved,
+			queued: state.queued(),
+			blocking,
+		}
+	}
+
+	/// Find the largest fraction of a movement that does not touch a solid tile
+	///
+	/// Bisects the movement until the distance between the last safe and the first
+	/// blocked fraction is at most `tolerance`, measured in tiles. Returns 1 if the whole
+	/// movement is free, and 0 if the object already touches a solid tile. A resolver
+	/// can scale its movement by the result and accept it right away. The object keeps
+	/// its orientation; use `time_of_impact_rotated` when it also turns.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// struct Dot(Vec<(f32, f32)>);
+	/// struct Move(Vector);
+	/// impl CollableState for Move {
+	///   fn queued(&self) -> Vector {
+	///     self.0
+	///   }
+	/// }
+	/// impl Collable<usize, Move> for Dot {
+	///   fn points(&self) -> Points {
+	///     Points::new(Vector(0.5, 0.5), &self.0)
+	///   }
+	///   fn resolve<I>(&mut self, _: TileSet<usize, I>, _: &mut Move) -> bool
+	///     where I: Iterator<Item = (i32, i32)>
+	///   {
+	///     true
+	///   }
+	/// }
+	/// let mut net: TileNet<usize> = TileNet::new(10, 1);
+	/// net.set(&1, (5, 0));
+	/// let dot = Dot(vec![(0.0, 0.0)]);
+	/// let fraction = dot.time_of_impact(&net, Vector(8.0, 0.0), 0.001, |x| *x == 1);
+	/// assert!((fraction * 8.0 - 4.5).abs() <= 0.001);
+	/// ```
+	fn time_of_impact<F>(&self,
+	                     net: &TileNet<T>,
+	                     movement: Vector,
+	                     tolerance: f32,
+	                     solid: F)
+	                     -> f32
+		where F: FnMut(&T) -> bool
+	{
+		self.time_of_impact_rotated(net, movement, 0.0, tolerance, solid)
+	}
+
+	/// Same as `time_of_impact`, but the object also rotates around its pivot
+	///
+	/// Every bisection step turns the object by the same fraction of `rotation` as
+	/// it moves, and traces it like `tiles_rotated`. The `tolerance` bounds the
+	/// distance that the furthest point travels between the safe and blocked poses.
+	fn time_of_impact_rotated<F>(&self,
+	                             net: &TileNet<T>,
+	                             movement: Vector,
+	                             rotation: f32,
+	                             tolerance: f32,
+	                             mut solid: F)
+	                             -> f32
+		where F: FnMut(&T) -> bool
+	{
+		assert!(tolerance > 0.0, "the tolerance must be positive");
+		let mut free = |fraction: f32| {
+			let mut partial = movement;
+			partial.scale(fraction);
+			!net.collide_set(self.tiles_rotated(partial, rotation * fraction)).any(&mut solid)
+		};
+		if free(1.0) {
+			return 1.0;
+		}
+		if !free(0.0) {
+			return 0.0;
+		}
+		let pivot = self.pivot();
+		let radius = self.points()
+			.map(|x| (Vector::from_tuple(x) - pivot).norm2sq())
+			.fold(0.0, f32::max)
+			.sqrt();
+		let length = movement.norm2sq().sqrt() + rotation.abs() * radius;
+		let (mut safe, mut blocked) = (0.0, 1.0);
+		while (blocked - safe) * length > tolerance {
+			let middle = (safe + blocked) / 2.0;
+			if middle <= safe || middle >= blocked {
 				break;
 			}
-			collided_once = true;
+			if free(middle) {
+				safe = middle;
+			} else {
+				blocked = middle;
+			}
 		}
-		self.postsolve(collided_once, resolved);
+		safe
 	}
 
 	/// Gives us a list of points, sorted by proximity on the line.
@@ -121,4 +379,247 @@ pub trait Collable<T> {
 		}
 		multi
 	}
+
+	/// Same as `tiles`, but the object also rotates around its pivot while it moves
+	///
+	/// Every point follows an arc, which is traced as a chain of straight lines
+	/// that are at most half a tile long. This keeps spinning bars and rotating
+	/// doors from skipping over the tiles between their start and end pose.
+	fn tiles_rotated(&self, queued: Vector, rotation: f32) -> MultiIter<(i32, i32)> {
+		if rotation == 0.0 {
+			return self.tiles(queued);
+		}
+		let pivot = self.pivot();
+		let arms: Vec<Vector> = self.points().map(|x| Vector::from_tuple(x) - pivot).collect();
+		let radius = arms.iter().map(|x| x.norm2sq()).fold(0.0, f32::max).sqrt();
+		let steps = (rotation.abs() * radius * 2.0).ceil().max(1.0) as usize;
+		let pose = |arm: Vector, step: usize| {
+			let fraction = step as f32 / steps as f32;
+			let (sin, cos) = (rotation * fraction).sin_cos();
+			Vector(pivot.0 + arm.0 * cos - arm.1 * sin + queued.0 * fraction,
+			       pivot.1 + arm.0 * sin + arm.1 * cos + queued.1 * fraction)
+		};
+		let mut multi = interleave!((i32, i32););
+
+		for arm in arms {
+			let lines: Vec<Line> =
+				(0..steps).map(|step| Line(pose(arm, step), pose(arm, step + 1))).collect();
+			multi.push(Box::new(lines.into_iter().flat_map(|x| x.supercover())));
+		}
+		multi
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct Mover(Vector);
+
+	impl CollableState for Mover {
+		fn queued(&self) -> Vector {
+			self.0
+		}
+	}
+
+	/// Unit square that halves its movement until nothing is in the way
+	struct Square {
+		points: Vec<(f32, f32)>,
+		position: Vector,
+	}
+
+	impl Collable<usize, Mover> for Square {
+		fn points(&self) -> Points {
+			Points::new(self.position, &self.points)
+		}
+
+		fn resolve<I>(&mut self, mut set: TileSet<usize, I>, state: &mut Mover) -> bool
+			where I: Iterator<Item = (i32, i32)>
+		{
+			if set.all(|x| *x == 0) {
+				self.position += state.0;
+				true
+			} else {
+				set.block();
+				state.0.scale(0.5);
+				false
+			}
+		}
+	}
+
+	fn square() -> Square {
+		Square {
+			points: vec![(0.0, 0.0), (0.9, 0.0), (0.0, 0.9), (0.9, 0.9)],
+			position: Vector(1.05, 1.05),
+		}
+	}
+
+	/// Bar from (2, 5) to (8, 5) that turns around its center
+	struct Bar {
+		points: Vec<(f32, f32)>,
+	}
+
+	struct Turn(f32);
+
+	impl CollableState for Turn {
+		fn queued(&self) -> Vector {
+			Vector(0.0, 0.0)
+		}
+
+		fn queued_rotation(&self) -> f32 {
+			self.0
+		}
+	}
+
+	impl Collable<usize, Turn> for Bar {
+		fn points(&self) -> Points {
+			Points::polygon(Vector(5.0, 5.0), &self.points)
+		}
+
+		fn resolve<I>(&mut self, mut set: TileSet<usize, I>, state: &mut Turn) -> bool
+			where I: Iterator<Item = (i32, i32)>
+		{
+			if set.all(|x| *x == 0) {
+				true
+			} else {
+				set.block();
+				state.0 = 0.0;
+				false
+			}
+		}
+	}
+
+	#[test]
+	fn rotation_follows_arcs() {
+		let bar = Bar { points: vec![(-3.0, 0.0), (3.0, 0.0)] };
+		let quarter = ::std::f32::consts::FRAC_PI_2;
+		let swept: Vec<(i32, i32)> = bar.tiles_rotated(Vector(0.0, 0.0), quarter).collect();
+		// The tip passes (7.12, 7.12) halfway, which the straight chord misses
+		assert!(swept.contains(&(7, 7)));
+		assert!(swept.contains(&(2, 2)));
+		assert!(!swept.contains(&(7, 2)));
+		let chord = Line(Vector(8.0, 5.0), Vector(5.0, 8.0));
+		assert!(!chord.supercover().any(|x| x == (7, 7)));
+
+		let mut net: TileNet<usize> = TileNet::new(10, 10);
+		net.set(&1, (7, 7));
+		let mut bar = bar;
+		let report = bar.solve(&net, &mut Turn(quarter));
+		assert_eq!(report.iterations, 2);
+		assert_eq!(report.blocking.len(), 1);
+		assert!(!bar.solve(&net, &mut Turn(-quarter)).exhausted);
+	}
+
+	#[test]
+	fn rotated_time_of_impact() {
+		let bar = Bar { points: vec![(-3.0, 0.0), (3.0, 0.0)] };
+		let quarter = ::std::f32::consts::FRAC_PI_2;
+		let mut net: TileNet<usize> = TileNet::new(10, 10);
+		net.set(&1, (7, 7));
+		let solid = |x: &usize| *x == 1;
+		// Without turning the bar never gets near the tile
+		assert_eq!(bar.time_of_impact(&net, Vector(0.0, 0.0), 0.001, solid), 1.0);
+		let fraction = bar.time_of_impact_rotated(&net, Vector(0.0, 0.0), quarter, 0.001, solid);
+		// The tip reaches y = 7 when the sine of its angle is 2/3
+		assert!((fraction * quarter - (2.0f32 / 3.0).asin()).abs() < 0.05);
+		let safe = bar.tiles_rotated(Vector(0.0, 0.0), fraction * quarter);
+		assert!(!net.collide_set(safe).any(solid));
+	}
+
+	#[test]
+	fn report() {
+		let mut net: TileNet<usize> = TileNet::new(10, 10);
+		net.set_col(&1, 5);
+		let mut collider = square();
+		let report = collider.solve(&net, &mut Mover(Vector(8.0, 0.0)));
+		assert_eq!(report.iterations, 3);
+		assert!(!report.exhausted);
+		assert_eq!(report.queued, Vector(2.0, 0.0));
+		assert!(report.blocking.iter().all(|x| x.0 == 5));
+		assert_eq!(collider.position, Vector(3.05, 1.05));
+	}
+
+	/// Dot that reads every tile on its way before deciding
+	struct Careful(Vector);
+
+	impl Collable<usize, Mover> for Careful {
+		fn points(&self) -> Points {
+			Points::owned(self.0, vec![(0.0, 0.0)])
+		}
+
+		fn resolve<I>(&mut self, mut set: TileSet<usize, I>, state: &mut Mover) -> bool
+			where I: Iterator<Item = (i32, i32)>
+		{
+			let mut blocked = false;
+			while let Some(tile) = set.next() {
+				if *tile == 1 {
+					set.block();
+					blocked = true;
+				}
+			}
+			if blocked {
+				state.0.scale(0.5);
+			} else {
+				self.0 += state.0;
+			}
+			!blocked
+		}
+	}
+
+	#[test]
+	fn blocking_named_by_resolver() {
+		let mut net: TileNet<usize> = TileNet::new(10, 3);
+		net.set(&1, (4, 1));
+		net.set(&1, (6, 1));
+		// The line runs out of the net, and every tile on it is read
+		let mut dot = Careful(Vector(0.5, 1.5));
+		let report = dot.solve(&net, &mut Mover(Vector(12.0, 0.0)));
+		assert_eq!(report.iterations, 3);
+		assert_eq!(report.blocking, vec![(4, 1), (6, 1)]);
+		assert_eq!(dot.0, Vector(3.5, 1.5));
+	}
+
+	#[test]
+	fn impact_within_tolerance() {
+		let mut net: TileNet<usize> = TileNet::new(10, 10);
+		net.set_col(&1, 5);
+		let collider = square();
+		for &tolerance in &[0.1, 0.01, 0.0001] {
+			let fraction = collider.time_of_impact(&net, Vector(8.0, 2.0), tolerance, |x| *x == 1);
+			let moved = fraction * 8.0;
+			assert!(moved < 3.05 && moved >= 3.05 - tolerance);
+		}
+		assert_eq!(collider.time_of_impact(&net, Vector(-1.0, 0.0), 0.1, |x| *x == 1), 1.0);
+		net.set(&1, (1, 1));
+		assert_eq!(collider.time_of_impact(&net, Vector(-1.0, 0.0), 0.1, |x| *x == 1), 0.0);
+	}
+
+	#[test]
+	fn edges_catch_pillars() {
+		let mut net: TileNet<usize> = TileNet::new(10, 10);
+		net.set(&1, (5, 4));
+		let corners = [(0.0, 0.0), (3.0, 0.0), (3.0, 3.0), (0.0, 3.0)];
+		let movement = Vector(0.0, 6.0);
+		let hits = |points: Points| {
+			let mut multi = interleave!((i32, i32););
+			for point in points {
+				let from = Vector::from_tuple(point);
+				multi.push(Box::new(Line(from, from + movement).supercover()));
+			}
+			net.collide_set(multi).any(|x| *x == 1)
+		};
+		assert!(!hits(Points::new(Vector(3.5, 0.5), &corners)));
+		assert!(hits(Points::polygon(Vector(3.5, 0.5), &corners)));
+	}
+
+	#[test]
+	fn iteration_limit() {
+		let mut net: TileNet<usize> = TileNet::new(10, 10);
+		net.set(&1, (1, 1));
+		let mut collider = square();
+		let report = collider.solve_with(&net, &mut Mover(Vector(1.0, 0.0)), 4);
+		assert_eq!(report.iterations, 4);
+		assert!(report.exhausted);
+		assert_eq!(report.blocking, vec![(1, 1)]);
+	}
 }
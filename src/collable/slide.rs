@@ -0,0 +1,213 @@
+use super::{TileNet, Vector};
+
+/// Outcome of `TileNet::slide_box`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Slide {
+	/// The new top left corner of the box
+	pub position: Vector,
+	/// The movement that was actually made
+	pub moved: Vector,
+	/// The sign of the movement along each axis that was stopped by a solid tile, or 0
+	///
+	/// Moving right into a wall gives 1 on the x axis, and landing on a floor gives 1
+	/// on the y axis.
+	pub blocked: (i32, i32),
+}
+
+impl Slide {
+	/// Check if the box was stopped while moving down, so it stands on a floor
+	pub fn on_ground(&self) -> bool {
+		self.blocked.1 > 0
+	}
+
+	/// Check if the box was stopped while moving up
+	pub fn on_ceiling(&self) -> bool {
+		self.blocked.1 < 0
+	}
+
+	/// Check if the box was stopped horizontally
+	pub fn on_wall(&self) -> bool {
+		self.blocked.0 != 0
+	}
+}
+
+impl<T> TileNet<T> {
+	/// Move an axis-aligned box, stopping flush against solid tiles
+	///
+	/// The box covers `position` up to, but not including, `position + size`, so a box
+	/// of size 1 at a whole coordinate covers exactly one tile. The box is moved along
+	/// the x axis first and then along the y axis. Each axis stops exactly at the
+	/// boundary of the first solid tile in the way, so the box slides along walls and
+	/// floors instead of stopping altogether. Tiles outside of the net are not solid.
+	///
+	/// A `Collable` shaped like a box can call this from `resolve` through
+	/// `TileSet::get_net`, and accept the movement right away.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<usize> = TileNet::new(10, 10);
+	/// net.set_row(&1, 5);
+	/// let size = Vector(1.0, 1.0);
+	/// let slide = net.slide_box(Vector(1.5, 3.5), size, Vector(2.0, 3.0), |x| *x == 1);
+	/// assert_eq!(slide.position, Vector(3.5, 4.0));
+	/// assert!(slide.on_ground());
+	/// assert!(!slide.on_wall());
+	/// ```
+	pub fn slide_box<F>(&self,
+	                    position: Vector,
+	                    size: Vector,
+	                    movement: Vector,
+	                    mut solid: F)
+	                    -> Slide
+		where F: FnMut(&T) -> bool
+	{
+		let mut blocked = (0, 0);
+		let mut position = position;
+		let start = position;
+
+		let rows = (position.1.floor() as i64, (position.1 + size.1).ceil() as i64);
+		let (x, hit) =
+			self.sweep(position.0, size.0, movement.0, rows, |col, row| (col, row), &mut solid);
+		position.0 = x;
+		if hit {
+			blocked.0 = movement.0.signum() as i32;
+		}
+
+		let cols = (position.0.floor() as i64, (position.0 + size.0).ceil() as i64);
+		let (y, hit) =
+			self.sweep(position.1, size.1, movement.1, cols, |row, col| (col, row), &mut solid);
+		position.1 = y;
+		if hit {
+			blocked.1 = movement.1.signum() as i32;
+		}
+
+		Slide {
+			position,
+			moved: position - start,
+			blocked,
+		}
+	}
+
+	/// Move an interval along one axis and stop it at the first solid line of tiles
+	///
+	/// `across` is the range of tiles the interval overlaps on the other axis, and
+	/// `tile` maps a coordinate along the axis and one across it to a tile.
+	fn sweep<F, G>(&self,
+	               start: f32,
+	               length: f32,
+	               movement: f32,
+	               across: (i64, i64),
+	               tile: G,
+	               solid: &mut F)
+	               -> (f32, bool)
+		where F: FnMut(&T) -> bool,
+		      G: Fn(i64, i64) -> (i64, i64)
+	{
+		let mut line_is_solid = |line: i64| {
+			(across.0..across.1).any(|other| {
+				let (x, y) = tile(line, other);
+				x >= 0 && y >= 0 &&
+				self.get((x as usize, y as usize)).map(&mut *solid).unwrap_or(false)
+			})
+		};
+		if movement > 0.0 {
+			let end = start + length;
+			for line in end.ceil() as i64..(end + movement).ceil() as i64 {
+				if line_is_solid(line) {
+					return (line as f32 - length, true);
+				}
+			}
+		} else if movement < 0.0 {
+			let mut line = start.floor() as i64 - 1;
+			while line >= (start + movement).floor() as i64 {
+				if line_is_solid(line) {
+					return (line as f32 + 1.0, true);
+				}
+				line -= 1;
+			}
+		}
+		(start + movement, false)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use super::super::{Collable, CollableState, Points, TileSet};
+
+	struct Mover(Vector);
+
+	impl CollableState for Mover {
+		fn queued(&self) -> Vector {
+			self.0
+		}
+	}
+
+	/// Unit box that slides instead of retrying with a shorter movement
+	struct Crate {
+		position: Vector,
+		slide: Option<Slide>,
+	}
+
+	impl Collable<usize, Mover> for Crate {
+		fn points(&self) -> Points<'static> {
+			Points::owned(self.position, vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)])
+		}
+
+		fn resolve<I>(&mut self, set: TileSet<usize, I>, state: &mut Mover) -> bool
+			where I: Iterator<Item = (i32, i32)>
+		{
+			let size = Vector(1.0, 1.0);
+			let slide = set.get_net().slide_box(self.position, size, state.0, |x| *x == 1);
+			self.position = slide.position;
+			self.slide = Some(slide);
+			true
+		}
+	}
+
+	#[test]
+	fn slides_along_wall() {
+		let mut net: TileNet<usize> = TileNet::new(10, 10);
+		net.set_col(&1, 6);
+		let size = Vector(1.0, 1.0);
+		let slide = net.slide_box(Vector(3.25, 2.0), size, Vector(4.0, 3.0), |x| *x == 1);
+		assert_eq!(slide.position, Vector(5.0, 5.0));
+		assert_eq!(slide.moved, Vector(1.75, 3.0));
+		assert_eq!(slide.blocked, (1, 0));
+		assert!(slide.on_wall());
+		// Flush against the wall, moving along it is free
+		let slide = net.slide_box(slide.position, size, Vector(0.0, -4.0), |x| *x == 1);
+		assert_eq!(slide.position, Vector(5.0, 1.0));
+		assert_eq!(slide.blocked, (0, 0));
+	}
+
+	#[test]
+	fn ceiling_and_left_wall() {
+		let mut net: TileNet<usize> = TileNet::new(10, 10);
+		net.set_row(&1, 0);
+		net.set_col(&1, 0);
+		let size = Vector(0.5, 0.5);
+		let slide = net.slide_box(Vector(4.2, 4.2), size, Vector(-10.0, -10.0), |x| *x == 1);
+		assert_eq!(slide.position, Vector(1.0, 1.0));
+		assert_eq!(slide.blocked, (-1, -1));
+		assert!(slide.on_ceiling());
+		assert!(!slide.on_ground());
+	}
+
+	#[test]
+	fn resolves_through_solve() {
+		let mut net: TileNet<usize> = TileNet::new(10, 10);
+		net.set_row(&1, 5);
+		net.set_col(&1, 6);
+		let mut body = Crate {
+			position: Vector(1.5, 2.0),
+			slide: None,
+		};
+		let report = body.solve(&net, &mut Mover(Vector(8.0, 6.0)));
+		assert_eq!(report.iterations, 1);
+		assert!(!report.exhausted);
+		assert_eq!(body.position, Vector(5.0, 4.0));
+		let slide = body.slide.unwrap();
+		assert!(slide.on_wall() && slide.on_ground());
+	}
+}
@@ -0,0 +1,59 @@
+/// Describe which tiles around a tile count as its neighbours
+///
+/// ```
+/// use tile_net::Connectivity;
+/// assert_eq!(Connectivity::Four.offsets().len(), 4);
+/// assert_eq!(Connectivity::Eight.offsets().len(), 8);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Connectivity {
+	/// Only left, right, up, and down
+	Four,
+	/// Orthogonal and diagonal neighbours
+	Eight,
+}
+
+static OFFSETS: [(i32, i32); 8] = [(1, 0), (0, 1), (-1, 0), (0, -1), (1, 1), (-1, 1), (-1, -1),
+                                   (1, -1)];
+
+impl Connectivity {
+	/// Get the offsets to the neighbours
+	///
+	/// The orthogonal offsets always come before the diagonal ones.
+	pub fn offsets(&self) -> &'static [(i32, i32)] {
+		match *self {
+			Connectivity::Four => &OFFSETS[..4],
+			Connectivity::Eight => &OFFSETS[..],
+		}
+	}
+}
+
+/// Measure of distance between two points on the grid
+///
+/// ```
+/// use tile_net::Metric;
+/// assert_eq!(Metric::Manhattan.distance(3.0, -4.0), 7.0);
+/// assert_eq!(Metric::Chebyshev.distance(3.0, -4.0), 4.0);
+/// assert_eq!(Metric::Euclidean.distance(3.0, -4.0), 5.0);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+	/// Sum of the axis distances
+	Manhattan,
+	/// Largest of the axis distances
+	Chebyshev,
+	/// Straight-line distance
+	Euclidean,
+}
+
+impl Metric {
+	/// Compute the length of the difference vector `(dx, dy)`
+	pub fn distance(&self, dx: f32, dy: f32) -> f32 {
+		let (dx, dy) = (dx.abs(), dy.abs());
+		match *self {
+			Metric::Manhattan => dx + dy,
+			Metric::Chebyshev => dx.max(dy),
+			Metric::Euclidean => (dx * dx + dy * dy).sqrt(),
+		}
+	}
+}
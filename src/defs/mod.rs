@@ -1,5 +1,7 @@
+pub mod grid;
 pub mod line;
 pub mod vector;
 
+pub use self::grid::{Connectivity, Metric};
 pub use self::line::{Line, SuperCover};
 pub use self::vector::Vector;
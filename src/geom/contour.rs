@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use super::super::{Connectivity, TileNet, Vector};
+
+/// Outline of a solid region, made from tile edges
+///
+/// Vertices are tile corners, where `(x, y)` is the top left corner of tile `(x, y)`.
+/// Only the corners where the outline turns are kept. With the y axis pointing
+/// down, the outer boundary runs clockwise and the holes run counterclockwise,
+/// so the solid tiles are always on the right hand side.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Contour {
+	/// The outer boundary
+	pub outer: Vec<(usize, usize)>,
+	/// The boundaries of the enclosed non-solid regions
+	pub holes: Vec<Vec<(usize, usize)>>,
+}
+
+/// Unit steps along the edges, indexed by direction: east, south, west, north
+static STEPS: [(i64, i64); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+impl<T> TileNet<T> {
+	/// Trace the outline of every solid region
+	///
+	/// Regions are 4-connected, so tiles that only touch at a corner get
+	/// separate contours. The contours are ordered by their top left solid tile.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<usize> = TileNet::new(6, 6);
+	/// net.set_box(&1, (1, 1), (4, 4));
+	/// net.set_box(&0, (2, 2), (3, 3));
+	/// let contours = net.contours(|x| *x == 1);
+	/// assert_eq!(contours.len(), 1);
+	/// assert_eq!(contours[0].outer, vec![(1, 1), (5, 1), (5, 5), (1, 5)]);
+	/// assert_eq!(contours[0].holes, vec![vec![(2, 2), (2, 4), (4, 4), (4, 2)]]);
+	/// ```
+	pub fn contours<F>(&self, mut solid: F) -> Vec<Contour>
+		where F: FnMut(&T) -> bool
+	{
+		let (cols, rows) = self.get_size();
+		let labels = self.label(Connectivity::Four, &mut solid);
+		let is_solid = |x: i64, y: i64| {
+			x >= 0 && y >= 0 && (x as usize) < cols && (y as usize) < rows &&
+			labels.grid.get((x as usize, y as usize)) != Some(&0)
+		};
+
+		// Bit `d` of a corner is set if an edge leaves the corner in direction `d`
+		let corners = cols + 1;
+		let mut edges = vec![0u8; corners * (rows + 1)];
+		for y in 0..rows as i64 {
+			for x in 0..cols as i64 {
+				if !is_solid(x, y) {
+					continue;
+				}
+				let (ux, uy) = (x as usize, y as usize);
+				if !is_solid(x, y - 1) {
+					edges[ux + uy * corners] |= 1;
+				}
+				if !is_solid(x + 1, y) {
+					edges[ux + 1 + uy * corners] |= 2;
+				}
+				if !is_solid(x, y + 1) {
+					edges[ux + 1 + (uy + 1) * corners] |= 4;
+				}
+				if !is_solid(x - 1, y) {
+					edges[ux + (uy + 1) * corners] |= 8;
+				}
+			}
+		}
+
+		let mut contours: Vec<Contour> = labels.components
+			.iter()
+			.map(|_| {
+				Contour {
+					outer: vec![],
+					holes: vec![],
+				}
+			})
+			.collect();
+		let mut used = vec![0u8; edges.len()];
+		for start in 0..edges.len() {
+			if edges[start] & !used[start] & 1 == 0 {
+				continue;
+			}
+			let (x, y) = (start % corners, start / corners);
+			let polygon = trace(&edges, &mut used, corners, (x, y));
+			// A loop starting with an eastward edge has a solid tile below its start
+			let label = *labels.grid.get((x, y)).unwrap();
+			let contour = &mut contours[label - 1];
+			if area(&polygon) > 0 {
+				contour.outer = polygon;
+			} else {
+				contour.holes.push(polygon);
+			}
+		}
+		contours
+	}
+
+	/// Trace the iso-lines of a scalar field with marching squares
+	///
+	/// The field is sampled at the center of every tile. Tiles whose value is at
+	/// least `threshold` count as inside. Each line is returned as a list of points
+	/// in tile coordinates; closed lines end with their first point. Saddle cells
+	/// are resolved with the average of their four samples.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<f32> = TileNet::new(5, 5);
+	/// net.set(&1.0, (2, 2));
+	/// let lines = net.marching_squares(0.5, |x| *x);
+	/// assert_eq!(lines.len(), 1);
+	/// assert_eq!(lines[0].len(), 5);
+	/// assert_eq!(lines[0].first(), lines[0].last());
+	/// assert!(lines[0].contains(&Vector(3.0, 2.5)));
+	/// ```
+	pub fn marching_squares<F>(&self, threshold: f32, mut value: F) -> Vec<Vec<Vector>>
+		where F: FnMut(&T) -> f32
+	{
+		let (cols, rows) = self.get_size();
+		let samples: Vec<f32> = self.get_raw().iter().map(&mut value).collect();
+		let sample = |x: usize, y: usize| samples[x + y * cols];
+		let inside = |x: usize, y: usize| sample(x, y) >= threshold;
+
+		// Crossings are identified by the sample pair they lie between
+		let mut points: HashMap<usize, Vector> = HashMap::new();
+		let mut links: HashMap<usize, Vec<usize>> = HashMap::new();
+		let mut crossing = |from: (usize, usize), to: (usize, usize), vertical: bool| {
+			let id = 2 * (from.0 + from.1 * cols) + vertical as usize;
+			let (a, b) = (sample(from.0, from.1), sample(to.0, to.1));
+			let t = (threshold - a) / (b - a);
+			let point = Vector(from.0 as f32 + 0.5 + t * (to.0 - from.0) as f32,
+			                   from.1 as f32 + 0.5 + t * (to.1 - from.1) as f32);
+			points.insert(id, point);
+			id
+		};
+		for y in 0..rows.saturating_sub(1) {
+			for x in 0..cols.saturating_sub(1) {
+				let corner =
+					[inside(x, y), inside(x + 1, y), inside(x + 1, y + 1), inside(x, y + 1)];
+				if corner.iter().all(|c| *c == corner[0]) {
+					continue;
+				}
+				let mut cut = |edge: usize| match edge {
+					0 => crossing((x, y), (x + 1, y), false),
+					1 => crossing((x + 1, y), (x + 1, y + 1), true),
+					2 => crossing((x, y + 1), (x + 1, y + 1), false),
+					_ => crossing((x, y), (x, y + 1), true),
+				};
+				let crossed: Vec<usize> =
+					(0..4).filter(|&i| corner[i] != corner[(i + 1) % 4]).collect();
+				let segments = if crossed.len() == 2 {
+					vec![(cut(crossed[0]), cut(crossed[1]))]
+				} else {
+					let average = (sample(x, y) + sample(x + 1, y) + sample(x + 1, y + 1) +
+					               sample(x, y + 1)) / 4.0;
+					if (average >= threshold) == corner[0] {
+						vec![(cut(0), cut(1)), (cut(2), cut(3))]
+					} else {
+						vec![(cut(3), cut(0)), (cut(1), cut(2))]
+					}
+				};
+				for (a, b) in segments {
+					links.entry(a).or_default().push(b);
+					links.entry(b).or_default().push(a);
+				}
+			}
+		}
+
+		let mut ids: Vec<usize> = links.keys().cloned().collect();
+		ids.sort_unstable();
+		// Open lines start at a crossing with a single neighbour, so trace those first
+		ids.sort_by_key(|id| links[id].len());
+		let mut lines = vec![];
+		for start in ids {
+			if links[&start].is_empty() {
+				continue;
+			}
+			let mut line = vec![points[&start]];
+			let mut current = start;
+			while let Some(next) = links.get_mut(&current).unwrap().pop() {
+				links.get_mut(&next).unwrap().retain(|x| *x != current);
+				line.push(points[&next]);
+				current = next;
+			}
+			lines.push(line);
+		}
+		lines
+	}
+}
+
+/// Follow the edges of a loop, preferring right turns so diagonal regions stay apart
+fn trace(edges: &[u8],
+         used: &mut [u8],
+         corners: usize,
+         start: (usize, usize))
+         -> Vec<(usize, usize)> {
+	let mut polygon = vec![];
+	let (mut x, mut y) = start;
+	let mut direction = 0;
+	loop {
+		used[x + y * corners] |= 1 << direction;
+		let step = STEPS[direction];
+		x = (x as i64 + step.0) as usize;
+		y = (y as i64 + step.1) as usize;
+		let out = edges[x + y * corners];
+		let turn = [1, 0, 3].iter()
+			.map(|turn| (direction + turn) % 4)
+			.find(|next| out & (1 << next) != 0)
+			.unwrap();
+		if turn != direction {
+			polygon.push((x, y));
+		}
+		direction = turn;
+		if (x, y) == start && direction == 0 {
+			break;
+		}
+	}
+	// Start at the top left corner of the loop
+	let first = (0..polygon.len()).min_by_key(|i| (polygon[*i].1, polygon[*i].0)).unwrap();
+	polygon.rotate_left(first);
+	polygon
+}
+
+/// Twice the signed area, positive for clockwise loops when y points down
+fn area(polygon: &[(usize, usize)]) -> i64 {
+	let mut sum = 0;
+	for (i, a) in polygon.iter().enumerate() {
+		let b = polygon[(i + 1) % polygon.len()];
+		sum += a.0 as i64 * b.1 as i64 - b.0 as i64 * a.1 as i64;
+	}
+	sum
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn diagonal_tiles_are_separate() {
+		let mut net: TileNet<usize> = TileNet::new(3, 3);
+		net.set(&1, (0, 0));
+		net.set(&1, (1, 1));
+		net.set(&1, (2, 0));
+		let contours = net.contours(|x| *x == 1);
+		assert_eq!(contours.len(), 3);
+		assert!(contours.iter().all(|x| x.outer.len() == 4 && x.holes.is_empty()));
+		assert_eq!(contours[2].outer, vec![(1, 1), (2, 1), (2, 2), (1, 2)]);
+	}
+
+	#[test]
+	fn island_in_hole() {
+		let mut net: TileNet<usize> = TileNet::new(7, 7);
+		net.set_box(&1, (0, 0), (6, 6));
+		net.set_box(&0, (1, 1), (5, 5));
+		net.set(&1, (3, 3));
+		let contours = net.contours(|x| *x == 1);
+		assert_eq!(contours.len(), 2);
+		assert_eq!(contours[0].outer, vec![(0, 0), (7, 0), (7, 7), (0, 7)]);
+		assert_eq!(contours[0].holes.len(), 1);
+		assert_eq!(contours[1].outer, vec![(3, 3), (4, 3), (4, 4), (3, 4)]);
+	}
+
+	#[test]
+	fn open_iso_line() {
+		let net: TileNet<f32> = TileNet::from_iter(4, (0..16).map(|i| (i % 4) as f32));
+		let lines = net.marching_squares(1.5, |x| *x);
+		assert_eq!(lines.len(), 1);
+		assert_eq!(lines[0].len(), 4);
+		assert!(lines[0].iter().all(|p| p.0 == 2.0));
+	}
+}
@@ -0,0 +1,126 @@
+use super::super::TileNet;
+
+/// Shape of the solid region at the end of an `Edge`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corner {
+	/// The solid region bends away, like the top corners of a platform
+	Convex,
+	/// The solid region bends inward, like where a floor meets a wall
+	Concave,
+}
+
+/// Straight run of exposed tile faces
+///
+/// Points are tile corners. The edge runs with the solid tiles on its right
+/// hand side when the y axis points down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edge {
+	/// The corner the edge starts at
+	pub start: (usize, usize),
+	/// The corner the edge ends at
+	pub end: (usize, usize),
+	/// Unit normal pointing away from the solid tiles
+	pub normal: (i32, i32),
+	/// The shape of the solid region at the start
+	pub start_corner: Corner,
+	/// The shape of the solid region at the end
+	pub end_corner: Corner,
+}
+
+impl Edge {
+	/// Get the number of tile faces in the edge
+	pub fn length(&self) -> usize {
+		let dx = (self.end.0 as i64 - self.start.0 as i64).abs();
+		let dy = (self.end.1 as i64 - self.start.1 as i64).abs();
+		(dx + dy) as usize
+	}
+}
+
+impl<T> TileNet<T> {
+	/// Extract the faces where solid tiles border non-solid tiles, merged into runs
+	///
+	/// Faces between two solid tiles are never returned, so a contact normal chosen
+	/// from these edges can not catch on the seam between two floor tiles.
+	/// Faces along the border of the net count as exposed.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<usize> = TileNet::new(8, 4);
+	/// net.set_row(&1, 3);
+	/// net.set(&1, (7, 2));
+	/// let edges = net.collision_edges(|x| *x == 1);
+	/// let floor = edges.iter().find(|x| x.normal == (0, -1) && x.start.1 == 3).unwrap();
+	/// assert_eq!((floor.start, floor.end), ((0, 3), (7, 3)));
+	/// assert_eq!(floor.start_corner, Corner::Convex);
+	/// assert_eq!(floor.end_corner, Corner::Concave);
+	/// ```
+	pub fn collision_edges<F>(&self, solid: F) -> Vec<Edge>
+		where F: FnMut(&T) -> bool
+	{
+		let mut edges = vec![];
+		for contour in self.contours(solid) {
+			for polygon in Some(&contour.outer).into_iter().chain(contour.holes.iter()) {
+				let count = polygon.len();
+				for i in 0..count {
+					let previous = polygon[(i + count - 1) % count];
+					let (start, end) = (polygon[i], polygon[(i + 1) % count]);
+					let next = polygon[(i + 2) % count];
+					let direction = heading(start, end);
+					edges.push(Edge {
+						start,
+						end,
+						normal: (direction.1, -direction.0),
+						start_corner: corner(heading(previous, start), direction),
+						end_corner: corner(direction, heading(end, next)),
+					});
+				}
+			}
+		}
+		edges
+	}
+}
+
+/// Unit direction of an axis-aligned segment
+fn heading(from: (usize, usize), to: (usize, usize)) -> (i32, i32) {
+	((to.0 as i64 - from.0 as i64).signum() as i32, (to.1 as i64 - from.1 as i64).signum() as i32)
+}
+
+/// With the solid tiles on the right, turning right goes around a convex corner
+fn corner(incoming: (i32, i32), outgoing: (i32, i32)) -> Corner {
+	if incoming.0 * outgoing.1 - incoming.1 * outgoing.0 > 0 {
+		Corner::Convex
+	} else {
+		Corner::Concave
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn no_interior_seams() {
+		let mut net: TileNet<usize> = TileNet::new(10, 10);
+		net.set_box(&1, (2, 2), (7, 7));
+		let edges = net.collision_edges(|x| *x == 1);
+		assert_eq!(edges.len(), 4);
+		assert!(edges.iter().all(|x| x.length() == 6));
+		assert!(edges.iter().all(|x| x.start_corner == Corner::Convex));
+		let normals: Vec<(i32, i32)> = edges.iter().map(|x| x.normal).collect();
+		assert_eq!(normals, vec![(0, -1), (1, 0), (0, 1), (-1, 0)]);
+	}
+
+	#[test]
+	fn hole_corners_are_concave() {
+		let mut net: TileNet<usize> = TileNet::new(5, 5);
+		net.set_box(&1, (0, 0), (4, 4));
+		net.set(&0, (2, 2));
+		let edges = net.collision_edges(|x| *x == 1);
+		let hole: Vec<&Edge> = edges.iter().filter(|x| x.length() == 1).collect();
+		assert_eq!(hole.len(), 4);
+		assert!(hole.iter().all(|x| x.start_corner == Corner::Concave));
+		// The normals of a hole point into the hole
+		let top = hole.iter().find(|x| x.start.1 == 2 && x.end.1 == 2).unwrap();
+		assert_eq!(top.normal, (0, 1));
+	}
+}
@@ -0,0 +1,264 @@
+use super::Rect;
+use super::super::{Span, TileNet};
+
+impl<T> TileNet<T> {
+	/// Merge the solid tiles into axis-aligned rectangles
+	///
+	/// Rows are scanned from the top. Every solid tile that is not covered yet
+	/// starts a rectangle that grows to the right as far as possible and then
+	/// downward for as long as the whole width stays solid. The rectangles do not
+	/// overlap and together cover exactly the solid tiles.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<usize> = TileNet::new(10, 10);
+	/// net.set_row(&1, 9);
+	/// net.set_box(&1, (2, 3), (4, 5));
+	/// let rects = net.greedy_mesh(|x| *x == 1);
+	/// assert_eq!(rects, vec![Rect { x: 2, y: 3, width: 3, height: 3 },
+	///                        Rect { x: 0, y: 9, width: 10, height: 1 }]);
+	/// ```
+	pub fn greedy_mesh<F>(&self, solid: F) -> Vec<Rect>
+		where F: FnMut(&T) -> bool
+	{
+		let (cols, rows) = self.get_size();
+		let mut open: Vec<bool> = self.get_raw().iter().map(solid).collect();
+		mesh(&mut open, cols, rows, (0, 0))
+	}
+}
+
+/// Rectangles of solid tiles that can be updated when tiles change
+///
+/// Keeps the rectangles of `TileNet::greedy_mesh` together with the rectangle
+/// that covers each tile. After a change only the rectangles touching the
+/// changed span are replaced, so the result may contain more rectangles than
+/// meshing the whole net again. Every rectangle has a stable index until it is
+/// removed, after which the index may be reused.
+///
+/// ```
+/// use tile_net::*;
+/// let mut net: TileNet<usize> = TileNet::new(10, 10);
+/// net.set_row(&1, 9);
+/// let mut mesh = GreedyMesh::new(&net, |x| *x == 1);
+/// assert_eq!(mesh.rects().count(), 1);
+/// let span = {
+///   let mut proxy = net.prepare();
+///   proxy.set(&0, (4, 9));
+///   proxy.get_span()
+/// };
+/// mesh.update(&net, span, |x| *x == 1);
+/// assert_eq!(mesh.rects().count(), 2);
+/// assert_eq!(mesh.rect_at((4, 9)), None);
+/// ```
+pub struct GreedyMesh {
+	rects: Vec<Option<Rect>>,
+	free: Vec<usize>,
+	owner: TileNet<Option<usize>>,
+}
+
+impl GreedyMesh {
+	/// Mesh a whole net
+	pub fn new<T, F>(net: &TileNet<T>, solid: F) -> GreedyMesh
+		where F: FnMut(&T) -> bool
+	{
+		let mut mesh = GreedyMesh {
+			rects: vec![],
+			free: vec![],
+			owner: TileNet::new(0, 0),
+		};
+		mesh.rebuild(net, solid);
+		mesh
+	}
+
+	/// Mesh the whole net again
+	pub fn rebuild<T, F>(&mut self, net: &TileNet<T>, solid: F)
+		where F: FnMut(&T) -> bool
+	{
+		let (cols, rows) = net.get_size();
+		self.rects.clear();
+		self.free.clear();
+		self.owner = TileNet::new(cols, rows);
+		for rect in net.greedy_mesh(solid) {
+			self.insert(rect);
+		}
+	}
+
+	/// Update the rectangles after the tiles within a span have changed
+	///
+	/// Returns the indices of the rectangles that were added. Rectangles that
+	/// overlapped the span are removed. If the net has been resized, the whole
+	/// net is meshed again.
+	pub fn update<T, F>(&mut self, net: &TileNet<T>, span: Span, mut solid: F) -> Vec<usize>
+		where F: FnMut(&T) -> bool
+	{
+		if net.get_size() != self.owner.get_size() {
+			self.rebuild(net, solid);
+			return (0..self.rects.len()).collect();
+		}
+		let (mut left, mut top, mut right, mut bottom) = match net.clamp_span(span, 0) {
+			Some(span) => span,
+			None => return vec![],
+		};
+
+		let mut removed = vec![];
+		for y in top..bottom + 1 {
+			for x in left..right + 1 {
+				if let Some(&Some(index)) = self.owner.get((x, y)) {
+					if !removed.contains(&index) {
+						removed.push(index);
+					}
+				}
+			}
+		}
+		for index in removed {
+			let rect = self.remove(index);
+			left = left.min(rect.x);
+			top = top.min(rect.y);
+			right = right.max(rect.x + rect.width - 1);
+			bottom = bottom.max(rect.y + rect.height - 1);
+		}
+
+		let (width, height) = (right - left + 1, bottom - top + 1);
+		let mut open = Vec::with_capacity(width * height);
+		for y in top..bottom + 1 {
+			for x in left..right + 1 {
+				let free = self.owner.get((x, y)) == Some(&None);
+				open.push(free && net.get((x, y)).map(&mut solid).unwrap_or(false));
+			}
+		}
+		mesh(&mut open, width, height, (left, top))
+			.into_iter()
+			.map(|rect| self.insert(rect))
+			.collect()
+	}
+
+	/// Iterate over the rectangles together with their indices
+	pub fn rects(&self) -> impl Iterator<Item = (usize, Rect)> + '_ {
+		self.rects.iter().enumerate().filter_map(|(index, rect)| rect.map(|x| (index, x)))
+	}
+
+	/// Get a rectangle by its index
+	pub fn get(&self, index: usize) -> Option<Rect> {
+		self.rects.get(index).cloned().and_then(|x| x)
+	}
+
+	/// Get the index of the rectangle covering a tile
+	pub fn rect_at(&self, p: (usize, usize)) -> Option<usize> {
+		self.owner.get(p).cloned().and_then(|x| x)
+	}
+
+	fn insert(&mut self, rect: Rect) -> usize {
+		let index = match self.free.pop() {
+			Some(index) => {
+				self.rects[index] = Some(rect);
+				index
+			}
+			None => {
+				self.rects.push(Some(rect));
+				self.rects.len() - 1
+			}
+		};
+		self.owner.set_box(&Some(index),
+		                   (rect.x, rect.y),
+		                   (rect.x + rect.width - 1, rect.y + rect.height - 1));
+		index
+	}
+
+	fn remove(&mut self, index: usize) -> Rect {
+		let rect = self.rects[index].take().unwrap();
+		self.free.push(index);
+		self.owner.set_box(&None,
+		                   (rect.x, rect.y),
+		                   (rect.x + rect.width - 1, rect.y + rect.height - 1));
+		rect
+	}
+}
+
+/// Greedily cover the open cells of a grid, closing them as they are covered
+///
+/// The rectangles are offset by `origin`.
+fn mesh(open: &mut [bool], cols: usize, rows: usize, origin: (usize, usize)) -> Vec<Rect> {
+	let mut rects = vec![];
+	for y in 0..rows {
+		for x in 0..cols {
+			if !open[x + y * cols] {
+				continue;
+			}
+			let mut width = 1;
+			while x + width < cols && open[x + width + y * cols] {
+				width += 1;
+			}
+			let mut height = 1;
+			while y + height < rows &&
+			      open[(y + height) * cols + x..][..width].iter().all(|x| *x) {
+				height += 1;
+			}
+			for row in y..y + height {
+				for cell in &mut open[x + row * cols..x + width + row * cols] {
+					*cell = false;
+				}
+			}
+			rects.push(Rect {
+				x: origin.0 + x,
+				y: origin.1 + y,
+				width,
+				height,
+			});
+		}
+	}
+	rects
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn covered(rects: &[Rect], cols: usize, rows: usize) -> Vec<bool> {
+		let mut grid = vec![false; cols * rows];
+		for rect in rects {
+			for y in rect.y..rect.y + rect.height {
+				for x in rect.x..rect.x + rect.width {
+					assert!(!grid[x + y * cols], "rectangles overlap");
+					grid[x + y * cols] = true;
+				}
+			}
+		}
+		grid
+	}
+
+	#[test]
+	fn update_covers_solids() {
+		let mut seed = 3u32;
+		let mut random = move || {
+			seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+			(seed >> 16) as usize
+		};
+		let mut net: TileNet<usize> = TileNet::from_iter(24, (0..24 * 16).map(|_| random() & 1));
+		let mut mesh = GreedyMesh::new(&net, |x| *x == 1);
+		for _ in 0..40 {
+			let (x, y) = (random() % 20, random() % 12);
+			let span = {
+				let mut proxy = net.prepare();
+				proxy.set_box(&(random() & 1), (x, y), (x + random() % 4, y + random() % 4));
+				proxy.get_span()
+			};
+			mesh.update(&net, span, |x| *x == 1);
+			let rects: Vec<Rect> = mesh.rects().map(|x| x.1).collect();
+			let solid: Vec<bool> = net.get_raw().iter().map(|x| *x == 1).collect();
+			assert_eq!(covered(&rects, 24, 16), solid);
+			for (index, rect) in mesh.rects() {
+				assert_eq!(mesh.rect_at((rect.x, rect.y)), Some(index));
+			}
+		}
+	}
+
+	#[test]
+	fn fewer_rectangles_than_tiles() {
+		let mut net: TileNet<usize> = TileNet::new(8, 8);
+		net.set_box(&1, (0, 0), (7, 3));
+		net.set_box(&1, (0, 4), (3, 7));
+		let rects = net.greedy_mesh(|x| *x == 1);
+		assert_eq!(rects.len(), 2);
+		assert_eq!(covered(&rects, 8, 8).iter().filter(|x| **x).count(), 48);
+	}
+}
@@ -0,0 +1,39 @@
+use super::Span;
+pub use self::contour::Contour;
+pub use self::edges::{Corner, Edge};
+pub use self::mesh::GreedyMesh;
+
+mod contour;
+mod edges;
+mod mesh;
+
+/// Axis-aligned rectangle of whole tiles
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rect {
+	/// The column of the left edge
+	pub x: usize,
+	/// The row of the top edge
+	pub y: usize,
+	/// The number of columns covered
+	pub width: usize,
+	/// The number of rows covered
+	pub height: usize,
+}
+
+impl Rect {
+	/// Check if the rectangle covers a tile
+	pub fn contains(&self, p: (usize, usize)) -> bool {
+		p.0 >= self.x && p.1 >= self.y && p.0 < self.x + self.width && p.1 < self.y + self.height
+	}
+
+	/// Check if the rectangle covers any tile of a span
+	pub fn overlaps(&self, span: Span) -> bool {
+		self.x <= span.2 && self.y <= span.3 && span.0 < self.x + self.width &&
+		span.1 < self.y + self.height
+	}
+
+	/// Get the number of tiles covered
+	pub fn area(&self) -> usize {
+		self.width * self.height
+	}
+}
@@ -431,13 +431,26 @@
 #[macro_use(interleave)]
 extern crate interleave;
 
+mod analysis;
 mod collable;
 mod defs;
+mod geom;
+mod path;
+mod shape;
 mod tiles;
+mod voxel;
 
-pub use defs::{SuperCover, Line, Vector};
-pub use collable::{Collable, Points};
-pub use tiles::{TileNet, TileNetProxy, TileView, TileSet};
+pub use analysis::{ColorLightMap, Component, Labels, LightMap, Lighting};
+pub use defs::{Connectivity, Metric, SuperCover, Line, Vector};
+pub use geom::{Contour, Corner, Edge, GreedyMesh, Rect};
+pub use collable::{Collable, CollableState, Points, Slide, SolveReport};
+pub use path::{AStar, Action, Agility, Corners, FlowField, Heuristic, JumpPointSearch, NavMesh,
+               PathError, PlatformEdge, PlatformGraph, Portal, Step};
+pub use shape::{Body, Contact, Impact, Shape, SweepAndPrune};
+pub use tiles::{Autotiler, Border, Matches, Neighbours, NeighboursMut, Pattern, PatternCell, Rule,
+                Span, TileNet, TileNetProxy, TileView, TileSet, Transform, WangTile};
+pub use voxel::{Line3, Vector3, VoxelCollable, VoxelCover, VoxelNet, VoxelPoints, VoxelReport,
+                VoxelSet, VoxelState};
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,165 @@
+use std::f32::consts::SQRT_2;
+use super::{Corners, Heuristic, Nodes, PathError, in_bounds};
+use super::super::{Connectivity, TileNet};
+
+/// A* path finder over a `TileNet`
+///
+/// The finder keeps its node buffers between queries, so reuse the same
+/// `AStar` for many queries to avoid reallocating.
+///
+/// The cost closure returns the cost of entering a tile, or `None` if the
+/// tile can not be entered. Diagonal steps cost the square root of 2 times
+/// the cost of the entered tile.
+///
+/// ```
+/// use tile_net::*;
+/// let mut net: TileNet<usize> = TileNet::new(10, 10);
+/// net.set_col(&1, 5);
+/// net.set(&0, (5, 9));
+/// let mut astar = AStar::new();
+/// astar.connectivity = Connectivity::Four;
+/// let path = astar.find(&net, (0, 0), (9, 0), |x| if *x == 0 { Some(1.0) } else { None })
+///   .unwrap();
+/// assert_eq!(path.first(), Some(&(0, 0)));
+/// assert_eq!(path.last(), Some(&(9, 0)));
+/// assert!(path.contains(&(5, 9)));
+/// ```
+pub struct AStar {
+	/// Which neighbours a tile is connected to
+	pub connectivity: Connectivity,
+	/// When diagonal steps may cut past corners
+	pub corners: Corners,
+	/// The estimate of the remaining cost
+	pub heuristic: Heuristic,
+	nodes: Nodes,
+}
+
+impl Default for AStar {
+	fn default() -> AStar {
+		AStar::new()
+	}
+}
+
+impl AStar {
+	/// Create a finder using 8-way movement without corner cutting and the octile heuristic
+	pub fn new() -> AStar {
+		AStar {
+			connectivity: Connectivity::Eight,
+			corners: Corners::IfBothOpen,
+			heuristic: Heuristic::Octile,
+			nodes: Nodes::new(),
+		}
+	}
+
+	/// Find the cheapest path from `start` to `goal`
+	///
+	/// The returned path contains every tile from `start` to `goal`, both inclusive.
+	pub fn find<T, F>(&mut self,
+	                  net: &TileNet<T>,
+	                  start: (usize, usize),
+	                  goal: (usize, usize),
+	                  mut cost: F)
+	                  -> Result<Vec<(usize, usize)>, PathError>
+		where F: FnMut(&T) -> Option<f32>
+	{
+		if !in_bounds(net, start) || !in_bounds(net, goal) {
+			return Err(PathError::OutOfBounds);
+		}
+		if net.get(start).and_then(&mut cost).is_none() {
+			return Err(PathError::StartBlocked);
+		}
+		if net.get(goal).and_then(&mut cost).is_none() {
+			return Err(PathError::GoalBlocked);
+		}
+		let (cols, rows) = net.get_size();
+		self.nodes.prepare(cols * rows);
+		let index = |p: (usize, usize)| p.0 + p.1 * cols;
+		let (start_index, goal_index) = (index(start), index(goal));
+		self.nodes.relax(start_index, start_index, 0.0, self.heuristic.estimate(start, goal));
+
+		while let Some(current) = self.nodes.pop() {
+			if current.index == goal_index {
+				return Ok(self.nodes.backtrack(cols, start_index, goal_index));
+			}
+			let here = (current.index % cols, current.index / cols);
+			for &offset in self.connectivity.offsets() {
+				let next = match net.offset(here, offset) {
+					Some(next) => next,
+					None => continue,
+				};
+				if self.nodes.is_closed(index(next)) {
+					continue;
+				}
+				let step = match net.get(next).and_then(&mut cost) {
+					Some(step) => step,
+					None => continue,
+				};
+				let step = if offset.0 != 0 && offset.1 != 0 {
+					let first = net.offset(here, (offset.0, 0))
+						.and_then(|p| net.get(p))
+						.and_then(&mut cost)
+						.is_some();
+					let second = net.offset(here, (0, offset.1))
+						.and_then(|p| net.get(p))
+						.and_then(&mut cost)
+						.is_some();
+					if !self.corners.allows(first, second) {
+						continue;
+					}
+					step * SQRT_2
+				} else {
+					step
+				};
+				self.nodes.relax(index(next),
+				                 current.index,
+				                 current.cost + step,
+				                 self.heuristic.estimate(next, goal));
+			}
+		}
+		Err(PathError::Unreachable)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn open(x: &usize) -> Option<f32> {
+		if *x == 0 { Some(1.0) } else { None }
+	}
+
+	#[test]
+	fn straight_line() {
+		let net: TileNet<usize> = TileNet::new(10, 3);
+		let mut astar = AStar::new();
+		let path = astar.find(&net, (0, 1), (9, 1), open).unwrap();
+		assert_eq!(path, (0..10).map(|x| (x, 1)).collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn corner_cutting() {
+		let mut net: TileNet<usize> = TileNet::new(2, 2);
+		net.set(&1, (1, 0));
+		net.set(&1, (0, 1));
+		let mut astar = AStar::new();
+		assert_eq!(astar.find(&net, (0, 0), (1, 1), open),
+		           Err(PathError::Unreachable));
+		astar.corners = Corners::Always;
+		assert_eq!(astar.find(&net, (0, 0), (1, 1), open),
+		           Ok(vec![(0, 0), (1, 1)]));
+	}
+
+	#[test]
+	fn errors_and_reuse() {
+		let mut net: TileNet<usize> = TileNet::new(5, 5);
+		net.set_col(&1, 2);
+		let mut astar = AStar::new();
+		assert_eq!(astar.find(&net, (0, 0), (5, 0), open), Err(PathError::OutOfBounds));
+		assert_eq!(astar.find(&net, (2, 0), (4, 0), open), Err(PathError::StartBlocked));
+		assert_eq!(astar.find(&net, (0, 0), (2, 4), open), Err(PathError::GoalBlocked));
+		assert_eq!(astar.find(&net, (0, 0), (4, 4), open), Err(PathError::Unreachable));
+		net.set(&0, (2, 4));
+		let path = astar.find(&net, (0, 0), (4, 4), open).unwrap();
+		assert!(path.contains(&(2, 4)));
+	}
+}
@@ -0,0 +1,316 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use super::super::{Connectivity, Span, TileNet};
+
+/// Distance map and direction field leading to a set of goals
+///
+/// The field is built with Dijkstra's algorithm from every goal at once.
+/// Each tile stores its integer distance to the nearest goal and the direction
+/// of the next step, so any number of agents can look up their next tile in
+/// constant time.
+///
+/// The cost closure returns the cost of entering a tile, or `None` if the tile
+/// can not be entered. With `Connectivity::Four` a step costs the cost of the
+/// entered tile. With `Connectivity::Eight` distances are counted in half steps:
+/// a straight step costs twice and a diagonal step three times the cost of the
+/// entered tile, and a diagonal step may not cut past a corner of an impassable
+/// tile. `step_scale` gives the factor of the straight steps.
+///
+/// ```
+/// use tile_net::*;
+/// let mut net: TileNet<usize> = TileNet::new(10, 10);
+/// net.set_col(&1, 5);
+/// net.set(&0, (5, 9));
+/// let cost = |x: &usize| if *x == 0 { Some(1) } else { None };
+/// let field = FlowField::new(&net, &[(9, 0)], Connectivity::Four, cost);
+/// assert_eq!(field.distance((9, 0)), Some(0));
+/// assert_eq!(field.distance((9, 2)), Some(2));
+/// assert_eq!(field.distance((5, 0)), None);
+/// assert_eq!(field.next_step((9, 1)), Some((9, 0)));
+/// ```
+pub struct FlowField {
+	connectivity: Connectivity,
+	goals: Vec<(usize, usize)>,
+	cost: TileNet<Option<u32>>,
+	distance: TileNet<u32>,
+	direction: TileNet<Option<(i32, i32)>>,
+	heap: BinaryHeap<Reverse<(u32, (usize, usize))>>,
+}
+
+impl FlowField {
+	/// Build a field leading to the goals
+	///
+	/// Goals that can not be entered are ignored.
+	pub fn new<T, F>(net: &TileNet<T>,
+	                 goals: &[(usize, usize)],
+	                 connectivity: Connectivity,
+	                 cost: F)
+	                 -> FlowField
+		where F: FnMut(&T) -> Option<u32>
+	{
+		let mut field = FlowField {
+			connectivity,
+			goals: goals.to_vec(),
+			cost: TileNet::new(0, 0),
+			distance: TileNet::new(0, 0),
+			direction: TileNet::new(0, 0),
+			heap: BinaryHeap::new(),
+		};
+		field.rebuild(net, cost);
+		field
+	}
+
+	/// Recompute the whole field
+	pub fn rebuild<T, F>(&mut self, net: &TileNet<T>, mut cost: F)
+		where F: FnMut(&T) -> Option<u32>
+	{
+		let (cols, rows) = net.get_size();
+		self.cost = TileNet::from_iter(cols.max(1), net.get_raw().iter().map(&mut cost));
+		self.distance = TileNet::from_iter(cols.max(1), (0..cols * rows).map(|_| u32::MAX));
+		self.direction = TileNet::new(cols, rows);
+		self.heap.clear();
+		for i in 0..self.goals.len() {
+			let goal = self.goals[i];
+			self.seed_goal(goal);
+		}
+		self.propagate();
+	}
+
+	/// Update the field after the tiles within a span have changed
+	///
+	/// Only the tiles whose route led through the span are recomputed.
+	/// If the net has been resized, the whole field is rebuilt.
+	pub fn update<T, F>(&mut self, net: &TileNet<T>, span: Span, mut cost: F)
+		where F: FnMut(&T) -> Option<u32>
+	{
+		if net.get_size() != self.cost.get_size() {
+			return self.rebuild(net, cost);
+		}
+		let (left, top, right, bottom) = match net.clamp_span(span, 0) {
+			Some(span) => span,
+			None => return,
+		};
+		for y in top..bottom + 1 {
+			for x in left..right + 1 {
+				self.cost.set(&net.get((x, y)).and_then(&mut cost), (x, y));
+			}
+		}
+
+		// A changed tile also affects the diagonal steps that pass its corners
+		let (left, top, right, bottom) = net.clamp_span(span, 1).unwrap();
+		let mut invalid = vec![];
+		for y in top..bottom + 1 {
+			for x in left..right + 1 {
+				self.distance.set(&u32::MAX, (x, y));
+				self.direction.set(&None, (x, y));
+				invalid.push((x, y));
+			}
+		}
+		let mut index = 0;
+		while index < invalid.len() {
+			let parent = invalid[index];
+			index += 1;
+			for &offset in self.connectivity.offsets() {
+				if let Some(child) = self.cost.offset(parent, offset) {
+					if self.direction.get(child) == Some(&Some((-offset.0, -offset.1))) {
+						self.invalidate(child, &mut invalid);
+					}
+				}
+			}
+		}
+
+		self.heap.clear();
+		for &tile in &invalid {
+			if self.goals.contains(&tile) {
+				self.seed_goal(tile);
+			}
+			for &offset in self.connectivity.offsets() {
+				if let Some(neighbour) = self.cost.offset(tile, offset) {
+					let distance = *self.distance.get(neighbour).unwrap();
+					if distance != u32::MAX {
+						self.heap.push(Reverse((distance, neighbour)));
+					}
+				}
+			}
+		}
+		self.propagate();
+	}
+
+	/// Get the distance from a tile to the nearest goal
+	///
+	/// The distance is in multiples of `step_scale`. Returns `None` if no goal can
+	/// be reached from the tile.
+	pub fn distance(&self, p: (usize, usize)) -> Option<u32> {
+		self.distance.get(p).cloned().filter(|x| *x != u32::MAX)
+	}
+
+	/// Get the distance of a straight step onto a tile of cost 1
+	///
+	/// This is 1 with `Connectivity::Four`, and 2 with `Connectivity::Eight` where
+	/// distances are counted in half steps.
+	pub fn step_scale(&self) -> u32 {
+		match self.connectivity {
+			Connectivity::Four => 1,
+			Connectivity::Eight => 2,
+		}
+	}
+
+	/// Get the direction of the next step towards the nearest goal
+	///
+	/// Returns `None` on goals and on tiles where no goal can be reached.
+	pub fn direction(&self, p: (usize, usize)) -> Option<(i32, i32)> {
+		self.direction.get(p).cloned().and_then(|x| x)
+	}
+
+	/// Get the next tile to step to from a tile
+	pub fn next_step(&self, p: (usize, usize)) -> Option<(usize, usize)> {
+		self.direction(p).and_then(|offset| self.distance.offset(p, offset))
+	}
+
+	/// Get the whole distance map, where `u32::MAX` marks unreachable tiles
+	pub fn distances(&self) -> &TileNet<u32> {
+		&self.distance
+	}
+
+	/// Get the whole direction field
+	pub fn directions(&self) -> &TileNet<Option<(i32, i32)>> {
+		&self.direction
+	}
+
+	fn seed_goal(&mut self, goal: (usize, usize)) {
+		if let Some(&Some(_)) = self.cost.get(goal) {
+			self.distance.set(&0, goal);
+			self.direction.set(&None, goal);
+			self.heap.push(Reverse((0, goal)));
+		}
+	}
+
+	fn invalidate(&mut self, p: (usize, usize), invalid: &mut Vec<(usize, usize)>) {
+		if *self.distance.get(p).unwrap() != u32::MAX {
+			self.distance.set(&u32::MAX, p);
+			self.direction.set(&None, p);
+			invalid.push(p);
+		}
+	}
+
+	/// Cost of stepping from `from` onto `to`, if the step is allowed
+	fn step(&self, from: (usize, usize), to: (usize, usize), offset: (i32, i32)) -> Option<u32> {
+		let cost = match self.cost.get(to) {
+			Some(&Some(cost)) => cost,
+			_ => return None,
+		};
+		if offset.0 == 0 || offset.1 == 0 {
+			return Some(cost.saturating_mul(self.step_scale()));
+		}
+		let open = |by: (i32, i32)| {
+			self.cost.offset(from, by).and_then(|p| *self.cost.get(p).unwrap()).is_some()
+		};
+		if open((offset.0, 0)) && open((0, offset.1)) {
+			Some(cost.saturating_mul(3))
+		} else {
+			None
+		}
+	}
+
+	/// Run Dijkstra's algorithm from the tiles on the heap
+	///
+	/// Searching outward from a goal, a step from `current` to `next` is
+	/// the agent's step from `next` onto `current`.
+	fn propagate(&mut self) {
+		while let Some(Reverse((distance, current))) = self.heap.pop() {
+			if distance > *self.distance.get(current).unwrap() {
+				continue;
+			}
+			for &offset in self.connectivity.offsets() {
+				let next = match self.cost.offset(current, offset) {
+					Some(next) => next,
+					None => continue,
+				};
+				if let Some(&None) = self.cost.get(next) {
+					continue;
+				}
+				let back = (-offset.0, -offset.1);
+				let step = match self.step(next, current, back) {
+					Some(step) => step,
+					None => continue,
+				};
+				let total = distance.saturating_add(step);
+				if total < *self.distance.get(next).unwrap() {
+					self.distance.set(&total, next);
+					self.direction.set(&Some(back), next);
+					self.heap.push(Reverse((total, next)));
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn cost(x: &u32) -> Option<u32> {
+		if *x == 0 { None } else { Some(*x) }
+	}
+
+	#[test]
+	fn update_matches_rebuild() {
+		let mut seed = 7u32;
+		let mut random = move || {
+			seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+			(seed >> 16) % 5
+		};
+		let mut net: TileNet<u32> = TileNet::from_iter(30, (0..30 * 30).map(|_| random()));
+		let goals = [(0, 0), (29, 29), (15, 3)];
+		for &connectivity in &[Connectivity::Four, Connectivity::Eight] {
+			let mut field = FlowField::new(&net, &goals, connectivity, cost);
+			for round in 0..20 {
+				let x = random() as usize * 6;
+				let y = random() as usize * 6 + round % 2;
+				let span = {
+					let mut proxy = net.prepare();
+					proxy.set_box(&(round as u32 % 3), (x, y), (x + 2, y + 1));
+					proxy.get_span()
+				};
+				field.update(&net, span, cost);
+				let fresh = FlowField::new(&net, &goals, connectivity, cost);
+				assert_eq!(field.distances().get_raw(), fresh.distances().get_raw());
+			}
+		}
+	}
+
+	#[test]
+	fn follow_directions() {
+		let net: TileNet<u32> = TileNet::from_iter(8, (0..64).map(|_| 1));
+		let field = FlowField::new(&net, &[(7, 7)], Connectivity::Eight, cost);
+		let mut at = (0, 0);
+		let mut steps = 0;
+		while let Some(next) = field.next_step(at) {
+			at = next;
+			steps += 1;
+		}
+		assert_eq!(at, (7, 7));
+		assert_eq!(steps, 7);
+	}
+
+	#[test]
+	fn diagonal_costs_more_than_straight() {
+		let net: TileNet<u32> = TileNet::from_iter(5, (0..25).map(|_| 1));
+		let field = FlowField::new(&net, &[(2, 2)], Connectivity::Eight, cost);
+		assert_eq!(field.distance((2, 1)), Some(2));
+		assert_eq!(field.distance((1, 1)), Some(3));
+		// Two straight steps beat a zig-zag of two diagonals
+		assert_eq!(field.distance((2, 0)), Some(4));
+		assert_eq!(field.direction((2, 0)), Some((0, 1)));
+		assert_eq!(field.step_scale(), 2);
+	}
+
+	#[test]
+	fn four_way_distance_in_tiles() {
+		let net: TileNet<u32> = TileNet::from_iter(5, (0..25).map(|_| 1));
+		let field = FlowField::new(&net, &[(0, 0)], Connectivity::Four, cost);
+		assert_eq!(field.step_scale(), 1);
+		assert_eq!(field.distance((4, 0)), Some(4));
+		assert_eq!(field.distance((3, 2)), Some(5));
+	}
+}
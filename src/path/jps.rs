@@ -0,0 +1,328 @@
+use super::{Heuristic, Nodes, PathError};
+use super::super::{Span, TileNet};
+
+/// Jump Point Search over a uniform-cost grid
+///
+/// Finds the same shortest paths as `AStar` with 8-way movement, the octile
+/// heuristic, and `Corners::IfBothOpen`, but only opens the jump points where
+/// the path may turn. This is much faster on large open maps.
+///
+/// The passability of every tile is evaluated once and stored. When tiles change,
+/// refresh the stored passability with the span reported by the `TileNetProxy`.
+///
+/// ```
+/// use tile_net::*;
+/// let mut net: TileNet<usize> = TileNet::new(20, 20);
+/// let open = |x: &usize| *x == 0;
+/// let mut jps = JumpPointSearch::new(&net, open);
+/// assert_eq!(jps.find((0, 0), (19, 19)).unwrap().len(), 20);
+///
+/// let span = {
+///   let mut proxy = net.prepare();
+///   proxy.set_col(&1, 10);
+///   proxy.get_span()
+/// };
+/// jps.refresh(&net, span, open);
+/// assert_eq!(jps.find((0, 0), (19, 19)), Err(PathError::Unreachable));
+/// ```
+pub struct JumpPointSearch {
+	cols: usize,
+	rows: usize,
+	passable: Vec<bool>,
+	nodes: Nodes,
+}
+
+impl JumpPointSearch {
+	/// Create a search over a net, storing the passability of every tile
+	pub fn new<T, F>(net: &TileNet<T>, passable: F) -> JumpPointSearch
+		where F: FnMut(&T) -> bool
+	{
+		let mut jps = JumpPointSearch {
+			cols: 0,
+			rows: 0,
+			passable: vec![],
+			nodes: Nodes::new(),
+		};
+		jps.rebuild(net, passable);
+		jps
+	}
+
+	/// Re-evaluate the passability of every tile
+	pub fn rebuild<T, F>(&mut self, net: &TileNet<T>, mut passable: F)
+		where F: FnMut(&T) -> bool
+	{
+		let (cols, rows) = net.get_size();
+		self.cols = cols;
+		self.rows = rows;
+		self.passable = net.get_raw().iter().map(&mut passable).collect();
+	}
+
+	/// Re-evaluate the passability of the tiles within a span
+	///
+	/// If the net has been resized since the last refresh, everything is rebuilt.
+	pub fn refresh<T, F>(&mut self, net: &TileNet<T>, span: Span, mut passable: F)
+		where F: FnMut(&T) -> bool
+	{
+		if net.get_size() != (self.cols, self.rows) {
+			return self.rebuild(net, passable);
+		}
+		if let Some((left, top, right, bottom)) = net.clamp_span(span, 0) {
+			for y in top..bottom + 1 {
+				for x in left..right + 1 {
+					if let Some(tile) = net.get((x, y)) {
+						self.passable[x + y * self.cols] = passable(tile);
+					}
+				}
+			}
+		}
+	}
+
+	/// Find the shortest path from `start` to `goal`
+	///
+	/// The returned path contains every tile from `start` to `goal`, both inclusive.
+	pub fn find(&mut self,
+	            start: (usize, usize),
+	            goal: (usize, usize))
+	            -> Result<Vec<(usize, usize)>, PathError> {
+		if start.0 >= self.cols || start.1 >= self.rows || goal.0 >= self.cols ||
+		   goal.1 >= self.rows {
+			return Err(PathError::OutOfBounds);
+		}
+		if !self.walkable(start.0 as i64, start.1 as i64) {
+			return Err(PathError::StartBlocked);
+		}
+		if !self.walkable(goal.0 as i64, goal.1 as i64) {
+			return Err(PathError::GoalBlocked);
+		}
+		let cols = self.cols;
+		let (start_index, goal_index) = (start.0 + start.1 * cols, goal.0 + goal.1 * cols);
+		let goal_i64 = (goal.0 as i64, goal.1 as i64);
+		self.nodes.prepare(cols * self.rows);
+		self.nodes.relax(start_index, start_index, 0.0, Heuristic::Octile.estimate(start, goal));
+
+		let mut directions = Vec::with_capacity(8);
+		while let Some(current) = self.nodes.pop() {
+			if current.index == goal_index {
+				let jump_points = self.nodes.backtrack(cols, start_index, goal_index);
+				return Ok(expand(&jump_points));
+			}
+			let here = (current.index % cols, current.index / cols);
+			let parent = self.nodes.parent[current.index];
+			let parent = (parent % cols, parent / cols);
+			self.directions(here, parent, &mut directions);
+
+			for &(dx, dy) in &directions {
+				let found = self.jump((here.0 as i64 + dx, here.1 as i64 + dy), (dx, dy), goal_i64);
+				if let Some((x, y)) = found {
+					let next = (x as usize, y as usize);
+					let cost = current.cost + Heuristic::Octile.estimate(here, next);
+					self.nodes.relax(next.0 + next.1 * cols,
+					                 current.index,
+					                 cost,
+					                 Heuristic::Octile.estimate(next, goal));
+				}
+			}
+		}
+		Err(PathError::Unreachable)
+	}
+
+	fn walkable(&self, x: i64, y: i64) -> bool {
+		x >= 0 && y >= 0 && (x as usize) < self.cols && (y as usize) < self.rows &&
+		self.passable[x as usize + y as usize * self.cols]
+	}
+
+	/// Collect the directions worth searching from a node, pruned by where we came from
+	fn directions(&self,
+	              here: (usize, usize),
+	              parent: (usize, usize),
+	              directions: &mut Vec<(i64, i64)>) {
+		directions.clear();
+		let (x, y) = (here.0 as i64, here.1 as i64);
+		let dx = (x - parent.0 as i64).signum();
+		let dy = (y - parent.1 as i64).signum();
+		let walk = |ox: i64, oy: i64| self.walkable(x + ox, y + oy);
+
+		if dx == 0 && dy == 0 {
+			for &(ox, oy) in &[(1, 0), (-1, 0), (0, 1), (0, -1)] {
+				if walk(ox, oy) {
+					directions.push((ox, oy));
+				}
+			}
+			for &(ox, oy) in &[(1, 1), (-1, 1), (-1, -1), (1, -1)] {
+				if walk(ox, 0) && walk(0, oy) {
+					directions.push((ox, oy));
+				}
+			}
+		} else if dx != 0 && dy != 0 {
+			let (walk_x, walk_y) = (walk(dx, 0), walk(0, dy));
+			if walk_y {
+				directions.push((0, dy));
+			}
+			if walk_x {
+				directions.push((dx, 0));
+			}
+			if walk_x && walk_y {
+				directions.push((dx, dy));
+			}
+		} else if dx != 0 {
+			let (next, up, down) = (walk(dx, 0), walk(0, -1), walk(0, 1));
+			if next {
+				directions.push((dx, 0));
+				if up {
+					directions.push((dx, -1));
+				}
+				if down {
+					directions.push((dx, 1));
+				}
+			}
+			if up {
+				directions.push((0, -1));
+			}
+			if down {
+				directions.push((0, 1));
+			}
+		} else {
+			let (next, left, right) = (walk(0, dy), walk(-1, 0), walk(1, 0));
+			if next {
+				directions.push((0, dy));
+				if left {
+					directions.push((-1, dy));
+				}
+				if right {
+					directions.push((1, dy));
+				}
+			}
+			if left {
+				directions.push((-1, 0));
+			}
+			if right {
+				directions.push((1, 0));
+			}
+		}
+	}
+
+	/// Move in a direction until a jump point, the goal, or a wall is found
+	fn jump(&self, from: (i64, i64), direction: (i64, i64), goal: (i64, i64)) -> Option<(i64, i64)> {
+		let (mut x, mut y) = from;
+		let (dx, dy) = direction;
+		if dx == 0 || dy == 0 {
+			return self.jump_straight(from, direction, goal);
+		}
+		loop {
+			if !self.walkable(x, y) {
+				return None;
+			}
+			if (x, y) == goal || self.jump_straight((x + dx, y), (dx, 0), goal).is_some() ||
+			   self.jump_straight((x, y + dy), (0, dy), goal).is_some() {
+				return Some((x, y));
+			}
+			if !self.walkable(x + dx, y) || !self.walkable(x, y + dy) {
+				return None;
+			}
+			x += dx;
+			y += dy;
+		}
+	}
+
+	fn jump_straight(&self,
+	                 from: (i64, i64),
+	                 direction: (i64, i64),
+	                 goal: (i64, i64))
+	                 -> Option<(i64, i64)> {
+		let (mut x, mut y) = from;
+		let (dx, dy) = direction;
+		loop {
+			if !self.walkable(x, y) {
+				return None;
+			}
+			if (x, y) == goal {
+				return Some((x, y));
+			}
+			let forced = if dx != 0 {
+				(self.walkable(x, y - 1) && !self.walkable(x - dx, y - 1)) ||
+				(self.walkable(x, y + 1) && !self.walkable(x - dx, y + 1))
+			} else {
+				(self.walkable(x - 1, y) && !self.walkable(x - 1, y - dy)) ||
+				(self.walkable(x + 1, y) && !self.walkable(x + 1, y - dy))
+			};
+			if forced {
+				return Some((x, y));
+			}
+			x += dx;
+			y += dy;
+		}
+	}
+}
+
+/// Fill in the tiles between consecutive jump points
+///
+/// Consecutive jump points always lie on a straight or diagonal line.
+fn expand(jump_points: &[(usize, usize)]) -> Vec<(usize, usize)> {
+	let mut path = vec![];
+	for pair in jump_points.windows(2) {
+		let (mut x, mut y) = (pair[0].0 as i64, pair[0].1 as i64);
+		let (to_x, to_y) = (pair[1].0 as i64, pair[1].1 as i64);
+		while (x, y) != (to_x, to_y) {
+			path.push((x as usize, y as usize));
+			x += (to_x - x).signum();
+			y += (to_y - y).signum();
+		}
+	}
+	path.extend(jump_points.last());
+	path
+}
+
+#[cfg(test)]
+mod tests {
+	use std::f32::consts::SQRT_2;
+	use super::*;
+	use super::super::super::AStar;
+
+	fn length(path: &[(usize, usize)]) -> f32 {
+		path.windows(2)
+			.map(|pair| if pair[0].0 != pair[1].0 && pair[0].1 != pair[1].1 { SQRT_2 } else { 1.0 })
+			.sum()
+	}
+
+	#[test]
+	fn matches_astar() {
+		let mut seed = 12345u32;
+		let net: TileNet<bool> = TileNet::from_iter(40,
+		                                            (0..40 * 40).map(|_| {
+			seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+			(seed >> 16) & 3 == 0
+		}));
+		let open = |x: &bool| !*x;
+		let mut jps = JumpPointSearch::new(&net, open);
+		let mut astar = AStar::new();
+		for &(start, goal) in &[((0, 0), (39, 39)), ((5, 30), (33, 2)), ((20, 20), (0, 39))] {
+			let expected = astar.find(&net, start, goal, |x| if *x { None } else { Some(1.0) });
+			let found = jps.find(start, goal);
+			match (expected, found) {
+				(Ok(expected), Ok(found)) => {
+					assert_eq!(found.first(), Some(&start));
+					assert_eq!(found.last(), Some(&goal));
+					assert!((length(&expected) - length(&found)).abs() < 1e-3);
+					assert!(found.iter().all(|p| !*net.get(*p).unwrap()));
+				}
+				(expected, found) => assert_eq!(expected.is_err(), found.is_err()),
+			}
+		}
+	}
+
+	#[test]
+	fn refresh_span() {
+		let mut net: TileNet<usize> = TileNet::new(10, 10);
+		let open = |x: &usize| *x == 0;
+		let mut jps = JumpPointSearch::new(&net, open);
+		let span = {
+			let mut proxy = net.prepare();
+			proxy.set_box(&1, (4, 0), (4, 8));
+			proxy.get_span()
+		};
+		jps.refresh(&net, span, open);
+		let path = jps.find((0, 0), (9, 0)).unwrap();
+		assert!(path.contains(&(4, 9)));
+		assert_eq!(path.len(), 21);
+	}
+}
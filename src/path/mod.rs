@@ -0,0 +1,232 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::error::Error;
+use std::f32::consts::SQRT_2;
+use std::fmt;
+use super::TileNet;
+pub use self::astar::AStar;
+pub use self::flow::FlowField;
+pub use self::jps::JumpPointSearch;
+pub use self::navmesh::{NavMesh, Portal};
+pub use self::platformer::{Action, Agility, PlatformEdge, PlatformGraph, Step};
+
+mod astar;
+mod flow;
+mod jps;
+mod navmesh;
+mod platformer;
+
+/// Rule deciding when a diagonal step may pass the corner of a tile
+///
+/// A diagonal step from `(x, y)` to `(x + dx, y + dy)` passes by the two
+/// orthogonal tiles `(x + dx, y)` and `(x, y + dy)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corners {
+	/// Always allow the diagonal step, even between two blocked tiles
+	Always,
+	/// Allow the step if at least one of the orthogonal tiles is passable
+	IfAnyOpen,
+	/// Allow the step only if both orthogonal tiles are passable
+	IfBothOpen,
+}
+
+impl Corners {
+	/// Check if a diagonal step is allowed given the passability of the two orthogonal tiles
+	pub fn allows(&self, first: bool, second: bool) -> bool {
+		match *self {
+			Corners::Always => true,
+			Corners::IfAnyOpen => first || second,
+			Corners::IfBothOpen => first && second,
+		}
+	}
+}
+
+/// Estimate of the remaining cost from a tile to the goal
+///
+/// The estimates assume that every passable tile costs at least `1.0`.
+/// Use `Heuristic::Zero` if your costs can be smaller, which turns the search into Dijkstra's.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Heuristic {
+	/// Sum of the axis distances, admissible for `Connectivity::Four`
+	Manhattan,
+	/// Largest of the axis distances
+	Chebyshev,
+	/// Diagonal distance where a diagonal step costs the square root of 2
+	Octile,
+	/// Straight-line distance
+	Euclidean,
+	/// No estimate at all
+	Zero,
+}
+
+impl Heuristic {
+	/// Estimate the cost between two tiles
+	pub fn estimate(&self, from: (usize, usize), to: (usize, usize)) -> f32 {
+		let dx = (from.0 as f32 - to.0 as f32).abs();
+		let dy = (from.1 as f32 - to.1 as f32).abs();
+		match *self {
+			Heuristic::Manhattan => dx + dy,
+			Heuristic::Chebyshev => dx.max(dy),
+			Heuristic::Octile => dx.max(dy) + (SQRT_2 - 1.0) * dx.min(dy),
+			Heuristic::Euclidean => (dx * dx + dy * dy).sqrt(),
+			Heuristic::Zero => 0.0,
+		}
+	}
+}
+
+/// Reason why a path query found no path
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathError {
+	/// The start or goal lies outside of the `TileNet`
+	OutOfBounds,
+	/// The start tile is not passable
+	StartBlocked,
+	/// The goal tile is not passable
+	GoalBlocked,
+	/// Every tile reachable from the start was searched without finding the goal
+	Unreachable,
+}
+
+impl fmt::Display for PathError {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str(match *self {
+			PathError::OutOfBounds => "start or goal is outside of the tilenet",
+			PathError::StartBlocked => "start tile is not passable",
+			PathError::GoalBlocked => "goal tile is not passable",
+			PathError::Unreachable => "goal is not reachable from the start",
+		})
+	}
+}
+
+impl Error for PathError {}
+
+fn in_bounds<T>(net: &TileNet<T>, p: (usize, usize)) -> bool {
+	let (cols, rows) = net.get_size();
+	p.0 < cols && p.1 < rows
+}
+
+/// Node buffers of a best-first search, reused between queries
+///
+/// Instead of clearing the buffers on every query, each query gets a new
+/// generation and entries stamped with an older generation count as unvisited.
+struct Nodes {
+	cost: Vec<f32>,
+	parent: Vec<usize>,
+	seen: Vec<u32>,
+	closed: Vec<u32>,
+	generation: u32,
+	open: BinaryHeap<Open>,
+}
+
+#[derive(Clone, Copy)]
+struct Open {
+	estimate: f32,
+	cost: f32,
+	index: usize,
+}
+
+impl PartialEq for Open {
+	fn eq(&self, other: &Open) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for Open {}
+
+impl PartialOrd for Open {
+	fn partial_cmp(&self, other: &Open) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Open {
+	// Reversed so the `BinaryHeap` pops the smallest estimate first.
+	// Ties prefer the node furthest along.
+	fn cmp(&self, other: &Open) -> Ordering {
+		other.estimate
+			.partial_cmp(&self.estimate)
+			.unwrap_or(Ordering::Equal)
+			.then_with(|| self.cost.partial_cmp(&other.cost).unwrap_or(Ordering::Equal))
+	}
+}
+
+impl Nodes {
+	fn new() -> Nodes {
+		Nodes {
+			cost: vec![],
+			parent: vec![],
+			seen: vec![],
+			closed: vec![],
+			generation: 0,
+			open: BinaryHeap::new(),
+		}
+	}
+
+	/// Size the buffers and invalidate the previous query
+	fn prepare(&mut self, length: usize) {
+		self.open.clear();
+		if self.cost.len() != length {
+			self.cost = vec![0.0; length];
+			self.parent = vec![0; length];
+			self.seen = vec![0; length];
+			self.closed = vec![0; length];
+			self.generation = 0;
+		}
+		self.generation = self.generation.wrapping_add(1);
+		if self.generation == 0 {
+			for stamp in self.seen.iter_mut().chain(self.closed.iter_mut()) {
+				*stamp = 0;
+			}
+			self.generation = 1;
+		}
+	}
+
+	/// Open a node if it is new or reached cheaper than before
+	fn relax(&mut self, index: usize, parent: usize, cost: f32, estimate: f32) {
+		if self.closed[index] == self.generation {
+			return;
+		}
+		if self.seen[index] != self.generation || cost < self.cost[index] {
+			self.seen[index] = self.generation;
+			self.cost[index] = cost;
+			self.parent[index] = parent;
+			self.open.push(Open {
+				estimate: cost + estimate,
+				cost,
+				index,
+			});
+		}
+	}
+
+	/// Pop the most promising node and close it
+	fn pop(&mut self) -> Option<Open> {
+		while let Some(current) = self.open.pop() {
+			if self.closed[current.index] != self.generation {
+				self.closed[current.index] = self.generation;
+				return Some(current);
+			}
+		}
+		None
+	}
+
+	fn is_closed(&self, index: usize) -> bool {
+		self.closed[index] == self.generation
+	}
+
+	/// Follow the parents from the goal back to the start, as coordinates
+	fn backtrack(&self, cols: usize, start: usize, goal: usize) -> Vec<(usize, usize)> {
+		self.chain(start, goal).into_iter().map(|x| (x % cols, x / cols)).collect()
+	}
+
+	/// Follow the parents from the goal back to the start, as indices
+	fn chain(&self, start: usize, goal: usize) -> Vec<usize> {
+		let mut path = vec![goal];
+		let mut current = goal;
+		while current != start {
+			current = self.parent[current];
+			path.push(current);
+		}
+		path.reverse();
+		path
+	}
+}
@@ -0,0 +1,415 @@
+use super::{Nodes, PathError};
+use super::super::{GreedyMesh, Rect, Span, TileNet, Vector};
+
+/// Opening between two neighbouring polygons of a `NavMesh`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Portal {
+	/// The polygon on the other side
+	pub to: usize,
+	/// One end of the shared edge
+	pub start: Vector,
+	/// The other end of the shared edge
+	pub end: Vector,
+	/// The direction in which the portal is crossed towards `to`
+	pub direction: (i32, i32),
+}
+
+/// Navigation mesh of convex polygons covering the walkable area
+///
+/// The polygons are the rectangles of a `GreedyMesh` over the tiles an agent
+/// of the given radius can stand in. The radius is rounded up to whole tiles,
+/// and every tile within that many tiles of a blocked tile, or of the border,
+/// is left out, so the agent can move anywhere inside the polygons.
+///
+/// Paths are found by searching the polygon graph with A* and pulling the
+/// resulting corridor tight with the funnel algorithm. The search measures the
+/// corridor between polygon centers, so the path is the shortest one within that
+/// corridor, but another corridor may hold a shorter path.
+///
+/// ```
+/// use tile_net::*;
+/// let mut net: TileNet<usize> = TileNet::new(10, 10);
+/// net.set_box(&1, (4, 0), (5, 7));
+/// let mut mesh = NavMesh::new(&net, 0.0, |x| *x == 0);
+/// let path = mesh.find(Vector(1.5, 1.5), Vector(8.5, 1.5)).unwrap();
+/// // The path goes around the bottom of the wall
+/// assert_eq!(path.len(), 4);
+/// assert_eq!(path[1], Vector(4.0, 8.0));
+/// assert_eq!(path[2], Vector(6.0, 8.0));
+/// ```
+pub struct NavMesh {
+	clearance: usize,
+	walkable: TileNet<bool>,
+	clear: TileNet<bool>,
+	mesh: GreedyMesh,
+	portals: Vec<Vec<Portal>>,
+	nodes: Nodes,
+}
+
+impl NavMesh {
+	/// Build the navigation mesh of a net
+	pub fn new<T, F>(net: &TileNet<T>, radius: f32, walkable: F) -> NavMesh
+		where F: FnMut(&T) -> bool
+	{
+		let (cols, rows) = net.get_size();
+		let mut navmesh = NavMesh {
+			clearance: radius.max(0.0).ceil() as usize,
+			walkable: TileNet::from_iter(cols.max(1), net.get_raw().iter().map(walkable)),
+			clear: TileNet::new(cols.max(1), rows),
+			mesh: GreedyMesh::new(&TileNet::<bool>::new(cols.max(1), rows), |x| *x),
+			portals: vec![],
+			nodes: Nodes::new(),
+		};
+		navmesh.build();
+		navmesh
+	}
+
+	/// Build the whole mesh again
+	pub fn rebuild<T, F>(&mut self, net: &TileNet<T>, walkable: F)
+		where F: FnMut(&T) -> bool
+	{
+		let (cols, rows) = net.get_size();
+		self.walkable = TileNet::from_iter(cols.max(1), net.get_raw().iter().map(walkable));
+		self.clear = TileNet::new(cols.max(1), rows);
+		self.build();
+	}
+
+	fn build(&mut self) {
+		let (cols, rows) = self.walkable.get_size();
+		if let Some(span) = self.clear.clamp_span((0, 0, cols - 1, rows.saturating_sub(1)), 0) {
+			self.erode(span);
+		}
+		self.mesh.rebuild(&self.clear, |x| *x);
+		self.portals.clear();
+		let indices: Vec<usize> = self.mesh.rects().map(|x| x.0).collect();
+		for index in indices {
+			self.connect(index);
+		}
+	}
+
+	/// Rebuild the part of the mesh affected by a change to the tiles within a span
+	///
+	/// If the net has been resized, the whole mesh is built again.
+	pub fn update<T, F>(&mut self, net: &TileNet<T>, span: Span, mut walkable: F)
+		where F: FnMut(&T) -> bool
+	{
+		if net.get_size() != self.walkable.get_size() {
+			return self.rebuild(net, walkable);
+		}
+		let (left, top, right, bottom) = match net.clamp_span(span, 0) {
+			Some(span) => span,
+			None => return,
+		};
+		for y in top..bottom + 1 {
+			for x in left..right + 1 {
+				let value = net.get((x, y)).map(&mut walkable).unwrap_or(false);
+				self.walkable.set(&value, (x, y));
+			}
+		}
+		let region = net.clamp_span(span, self.clearance).unwrap();
+		self.erode(region);
+
+		// Find the extent of the polygons that are about to be replaced
+		let (mut left, mut top, mut right, mut bottom) = region;
+		for y in region.1..region.3 + 1 {
+			for x in region.0..region.2 + 1 {
+				if let Some(rect) = self.mesh.rect_at((x, y)).and_then(|x| self.mesh.get(x)) {
+					left = left.min(rect.x);
+					top = top.min(rect.y);
+					right = right.max(rect.x + rect.width - 1);
+					bottom = bottom.max(rect.y + rect.height - 1);
+				}
+			}
+		}
+		let before: Vec<usize> = self.mesh.rects().map(|x| x.0).collect();
+		self.mesh.update(&self.clear, region, |x| *x);
+		for index in before {
+			if self.mesh.get(index).is_none() {
+				self.portals[index].clear();
+			}
+		}
+
+		// Every polygon next to a replaced one has a tile next to the replaced area
+		let (left, top, right, bottom) = net.clamp_span((left, top, right, bottom), 1).unwrap();
+		let mut touched = vec![];
+		for y in top..bottom + 1 {
+			for x in left..right + 1 {
+				if let Some(index) = self.mesh.rect_at((x, y)) {
+					if !touched.contains(&index) {
+						touched.push(index);
+					}
+				}
+			}
+		}
+		for index in touched {
+			self.connect(index);
+		}
+	}
+
+	/// Iterate over the polygons together with their indices
+	pub fn polygons(&self) -> impl Iterator<Item = (usize, Rect)> + '_ {
+		self.mesh.rects()
+	}
+
+	/// Get the portals leading out of a polygon
+	pub fn portals(&self, index: usize) -> &[Portal] {
+		self.portals.get(index).map(|x| &x[..]).unwrap_or(&[])
+	}
+
+	/// Find the polygon containing a point
+	pub fn locate(&self, p: Vector) -> Option<usize> {
+		if p.0 < 0.0 || p.1 < 0.0 {
+			return None;
+		}
+		self.mesh.rect_at((p.0 as usize, p.1 as usize))
+	}
+
+	/// Find a path between two points through the polygon graph
+	///
+	/// A* picks the corridor of polygons with the shortest route between their
+	/// centers, and the funnel algorithm pulls the path through it tight. The
+	/// path starts at `start`, ends at `goal`, and has a point at every corner it
+	/// bends around.
+	pub fn find(&mut self, start: Vector, goal: Vector) -> Result<Vec<Vector>, PathError> {
+		let (cols, rows) = self.walkable.get_size();
+		let inside = |p: Vector| p.0 >= 0.0 && p.1 >= 0.0 && p.0 < cols as f32 && p.1 < rows as f32;
+		if !inside(start) || !inside(goal) {
+			return Err(PathError::OutOfBounds);
+		}
+		let from = self.locate(start).ok_or(PathError::StartBlocked)?;
+		let to = self.locate(goal).ok_or(PathError::GoalBlocked)?;
+
+		let slots = self.portals.len().max(from + 1).max(to + 1);
+		self.nodes.prepare(slots);
+		self.nodes.relax(from, from, 0.0, distance(start, goal));
+		while let Some(current) = self.nodes.pop() {
+			if current.index == to {
+				let corridor = self.nodes.chain(from, to);
+				return Ok(self.funnel(&corridor, start, goal));
+			}
+			let here = if current.index == from { start } else { self.center(current.index) };
+			for portal in &self.portals[current.index] {
+				let there = self.center(portal.to);
+				self.nodes.relax(portal.to,
+				                 current.index,
+				                 current.cost + distance(here, there),
+				                 distance(there, goal));
+			}
+		}
+		Err(PathError::Unreachable)
+	}
+
+	fn center(&self, index: usize) -> Vector {
+		let rect = self.mesh.get(index).unwrap();
+		Vector(rect.x as f32 + rect.width as f32 / 2.0,
+		       rect.y as f32 + rect.height as f32 / 2.0)
+	}
+
+	/// Recompute which tiles are far enough from blocked tiles
+	fn erode(&mut self, span: Span) {
+		let (cols, rows) = self.walkable.get_size();
+		let k = self.clearance;
+		for y in span.1..span.3 + 1 {
+			for x in span.0..span.2 + 1 {
+				let clear = x >= k && y >= k && x + k < cols && y + k < rows &&
+				            (y - k..y + k + 1).all(|y| {
+					(x - k..x + k + 1).all(|x| self.walkable.get((x, y)) == Some(&true))
+				});
+				self.clear.set(&clear, (x, y));
+			}
+		}
+	}
+
+	/// Recompute the portals leading out of a polygon
+	fn connect(&mut self, index: usize) {
+		let rect = self.mesh.get(index).unwrap();
+		let (cols, rows) = self.walkable.get_size();
+		let mut portals = vec![];
+		{
+			let mut side = |outside: Option<usize>, vertical: bool, direction: (i32, i32)| {
+				let (line, first, length) = if vertical {
+					(outside, rect.y, rect.height)
+				} else {
+					(outside, rect.x, rect.width)
+				};
+				let line = match line {
+					Some(line) => line,
+					None => return,
+				};
+				let edge = if direction.0 + direction.1 > 0 { line } else { line + 1 };
+				let point = |along: usize| if vertical {
+					Vector(edge as f32, along as f32)
+				} else {
+					Vector(along as f32, edge as f32)
+				};
+				let mut run: Option<(usize, usize)> = None;
+				for along in first..first + length + 1 {
+					let tile = if vertical { (line, along) } else { (along, line) };
+					let owner = if along < first + length { self.mesh.rect_at(tile) } else { None };
+					match run {
+						Some((to, _)) if owner == Some(to) => continue,
+						Some((to, begin)) => {
+							portals.push(Portal {
+								to,
+								start: point(begin),
+								end: point(along),
+								direction,
+							});
+						}
+						None => {}
+					}
+					run = owner.map(|to| (to, along));
+				}
+			};
+			let right = rect.x + rect.width;
+			let bottom = rect.y + rect.height;
+			side(Some(right).filter(|x| *x < cols), true, (1, 0));
+			side(rect.x.checked_sub(1), true, (-1, 0));
+			side(Some(bottom).filter(|y| *y < rows), false, (0, 1));
+			side(rect.y.checked_sub(1), false, (0, -1));
+		}
+		if self.portals.len() <= index {
+			self.portals.resize(index + 1, vec![]);
+		}
+		self.portals[index] = portals;
+	}
+
+	/// Pull a corridor of polygons tight with the simple stupid funnel algorithm
+	fn funnel(&self, corridor: &[usize], start: Vector, goal: Vector) -> Vec<Vector> {
+		let mut gates = vec![(start, start)];
+		for pair in corridor.windows(2) {
+			let portal = self.portals[pair[0]].iter().find(|x| x.to == pair[1]).unwrap();
+			let forward = Vector(portal.direction.0 as f32, portal.direction.1 as f32);
+			if cross(forward, portal.end - portal.start) > 0.0 {
+				gates.push((portal.end, portal.start));
+			} else {
+				gates.push((portal.start, portal.end));
+			}
+		}
+		gates.push((goal, goal));
+
+		let mut path = vec![start];
+		let (mut apex, mut left, mut right) = (start, start, start);
+		let (mut left_index, mut right_index) = (0, 0);
+		let mut i = 1;
+		while i < gates.len() {
+			let (next_left, next_right) = gates[i];
+			i += 1;
+			if cross(right - apex, next_right - apex) >= 0.0 {
+				if apex == right || cross(left - apex, next_right - apex) < 0.0 {
+					right = next_right;
+					right_index = i - 1;
+				} else {
+					apex = left;
+					path.push(apex);
+					right = apex;
+					right_index = left_index;
+					i = left_index + 1;
+					continue;
+				}
+			}
+			if cross(left - apex, next_left - apex) <= 0.0 {
+				if apex == left || cross(right - apex, next_left - apex) > 0.0 {
+					left = next_left;
+					left_index = i - 1;
+				} else {
+					apex = right;
+					path.push(apex);
+					left = apex;
+					left_index = right_index;
+					i = right_index + 1;
+					continue;
+				}
+			}
+		}
+		if path.last() != Some(&goal) {
+			path.push(goal);
+		}
+		path
+	}
+}
+
+fn cross(a: Vector, b: Vector) -> f32 {
+	a.0 * b.1 - a.1 * b.0
+}
+
+fn distance(a: Vector, b: Vector) -> f32 {
+	(a - b).norm2sq().sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use super::super::super::Border;
+
+	fn world() -> TileNet<usize> {
+		let mut net: TileNet<usize> = TileNet::new(20, 12);
+		net.set_box(&1, (6, 0), (7, 8));
+		net.set_box(&1, (13, 3), (14, 11));
+		net
+	}
+
+	#[test]
+	fn radius_keeps_distance() {
+		let net = world();
+		let mut mesh = NavMesh::new(&net, 0.8, |x| *x == 0);
+		for (_, rect) in mesh.polygons() {
+			for y in rect.y..rect.y + rect.height {
+				for x in rect.x..rect.x + rect.width {
+					assert!(net.ring((x, y), 1, Border::Skip).all(|x| *x.1 == 0));
+				}
+			}
+		}
+		let path = mesh.find(Vector(2.5, 2.5), Vector(17.5, 8.5)).unwrap();
+		assert_eq!(path,
+		           vec![Vector(2.5, 2.5),
+		                Vector(5.0, 10.0),
+		                Vector(9.0, 10.0),
+		                Vector(12.0, 2.0),
+		                Vector(16.0, 2.0),
+		                Vector(17.5, 8.5)]);
+	}
+
+	#[test]
+	fn straight_across_polygons() {
+		let mut net: TileNet<usize> = TileNet::new(10, 10);
+		net.set_box(&1, (5, 0), (5, 4));
+		let mut mesh = NavMesh::new(&net, 0.0, |x| *x == 0);
+		let (start, goal) = (Vector(1.5, 8.5), Vector(8.5, 6.5));
+		assert_ne!(mesh.locate(start), mesh.locate(goal));
+		assert_eq!(mesh.find(start, goal).unwrap(), vec![start, goal]);
+	}
+
+	#[test]
+	fn update_matches_rebuild() {
+		let mut net = world();
+		let mut mesh = NavMesh::new(&net, 0.5, |x| *x == 0);
+		assert!(mesh.find(Vector(2.5, 2.5), Vector(17.5, 8.5)).is_ok());
+		let span = {
+			let mut proxy = net.prepare();
+			proxy.set_box(&1, (6, 9), (7, 11));
+			proxy.get_span()
+		};
+		mesh.update(&net, span, |x| *x == 0);
+		assert_eq!(mesh.find(Vector(2.5, 2.5), Vector(17.5, 8.5)), Err(PathError::Unreachable));
+		let span = {
+			let mut proxy = net.prepare();
+			proxy.set_box(&0, (6, 4), (7, 6));
+			proxy.get_span()
+		};
+		mesh.update(&net, span, |x| *x == 0);
+		let fresh = NavMesh::new(&net, 0.5, |x| *x == 0);
+		for y in 0..12 {
+			for x in 0..20 {
+				let p = Vector(x as f32 + 0.5, y as f32 + 0.5);
+				assert_eq!(mesh.locate(p).is_some(), fresh.locate(p).is_some());
+			}
+		}
+		for (index, _) in mesh.polygons() {
+			for portal in mesh.portals(index) {
+				assert!(mesh.portals(portal.to).iter().any(|x| x.to == index));
+			}
+		}
+		assert!(mesh.find(Vector(2.5, 2.5), Vector(17.5, 8.5)).is_ok());
+	}
+}
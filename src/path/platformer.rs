@@ -0,0 +1,343 @@
+use super::{Nodes, PathError};
+use super::super::{Line, TileNet, Vector};
+
+/// Physical abilities of a platformer agent
+///
+/// The y axis points downward like in the tile grid, so gravity is positive and
+/// a jump starts with a negative vertical speed. Distances are in tiles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Agility {
+	/// The height of the highest jump
+	pub jump_height: f32,
+	/// The horizontal speed when running
+	pub run_speed: f32,
+	/// The downward acceleration
+	pub gravity: f32,
+	/// The time between two sampled points of a trajectory
+	pub time_step: f32,
+}
+
+impl Default for Agility {
+	fn default() -> Agility {
+		Agility {
+			jump_height: 3.2,
+			run_speed: 6.0,
+			gravity: 30.0,
+			time_step: 1.0 / 30.0,
+		}
+	}
+}
+
+/// The action that moves an agent along an edge of a `PlatformGraph`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+	/// Walk to the adjacent tile
+	Walk,
+	/// Jump off with the given initial velocity
+	Jump(Vector),
+	/// Walk off a ledge with the given horizontal velocity
+	Fall(Vector),
+}
+
+/// Edge from a standable tile to another
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlatformEdge {
+	/// The tile the agent ends up standing on
+	pub to: (usize, usize),
+	/// How to get there
+	pub action: Action,
+	/// The time it takes
+	pub cost: f32,
+}
+
+/// A single step of a platformer path
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Step {
+	/// The tile the agent stands on before the step
+	pub from: (usize, usize),
+	/// The tile the agent stands on after the step
+	pub to: (usize, usize),
+	/// How to get there
+	pub action: Action,
+}
+
+/// Horizontal speeds tried for jumps and falls, as fractions of the run speed
+static SPEEDS: [f32; 4] = [0.25, 0.5, 0.75, 1.0];
+
+/// The most time steps simulated along one trajectory
+const MAX_STEPS: usize = 4096;
+
+/// Movement graph for agents that walk, jump, and fall
+///
+/// The nodes are the standable tiles: passable tiles with a solid tile below.
+/// Jump and fall edges are found by simulating trajectories and tracing the
+/// line between every two samples with `SuperCover`. The agent is treated as a
+/// point at the center of the tile it stands on. Tiles outside of the net are solid.
+///
+/// ```
+/// use tile_net::*;
+/// let mut net: TileNet<usize> = TileNet::new(12, 8);
+/// net.set_row(&1, 7);
+/// net.set_box(&1, (6, 5), (11, 6));
+/// let mut graph = PlatformGraph::new(&net, Agility::default(), |x| *x == 1);
+/// let path = graph.find((1, 6), (8, 4)).unwrap();
+/// assert!(path.iter().any(|x| matches!(x.action, Action::Jump(_))));
+/// assert_eq!(path.last().unwrap().to, (8, 4));
+/// ```
+pub struct PlatformGraph {
+	cols: usize,
+	rows: usize,
+	agility: Agility,
+	solid: Vec<bool>,
+	edges: Vec<Vec<PlatformEdge>>,
+	nodes: Nodes,
+}
+
+impl PlatformGraph {
+	/// Build the movement graph of a net
+	///
+	/// Panics if the gravity or the time step of `agility` is not positive, since
+	/// trajectories would then never come down.
+	pub fn new<T, F>(net: &TileNet<T>, agility: Agility, solid: F) -> PlatformGraph
+		where F: FnMut(&T) -> bool
+	{
+		assert!(agility.gravity > 0.0, "the gravity must be positive");
+		assert!(agility.time_step > 0.0, "the time step must be positive");
+		let (cols, rows) = net.get_size();
+		let mut graph = PlatformGraph {
+			cols,
+			rows,
+			agility,
+			solid: net.get_raw().iter().map(solid).collect(),
+			edges: vec![],
+			nodes: Nodes::new(),
+		};
+		graph.edges = (0..cols * rows).map(|i| graph.connect((i % cols, i / cols))).collect();
+		graph
+	}
+
+	/// Check if an agent can stand on a tile
+	pub fn is_standable(&self, p: (usize, usize)) -> bool {
+		let (x, y) = (p.0 as i64, p.1 as i64);
+		p.0 < self.cols && p.1 < self.rows && !self.is_solid(x, y) && self.is_solid(x, y + 1)
+	}
+
+	/// Get the edges leaving a tile
+	pub fn edges(&self, p: (usize, usize)) -> &[PlatformEdge] {
+		if p.0 < self.cols && p.1 < self.rows {
+			&self.edges[p.0 + p.1 * self.cols]
+		} else {
+			&[]
+		}
+	}
+
+	/// Find the fastest path between two standable tiles
+	pub fn find(&mut self,
+	            start: (usize, usize),
+	            goal: (usize, usize))
+	            -> Result<Vec<Step>, PathError> {
+		if start.0 >= self.cols || start.1 >= self.rows || goal.0 >= self.cols ||
+		   goal.1 >= self.rows {
+			return Err(PathError::OutOfBounds);
+		}
+		if !self.is_standable(start) {
+			return Err(PathError::StartBlocked);
+		}
+		if !self.is_standable(goal) {
+			return Err(PathError::GoalBlocked);
+		}
+		let cols = self.cols;
+		let (start_index, goal_index) = (start.0 + start.1 * cols, goal.0 + goal.1 * cols);
+		self.nodes.prepare(cols * self.rows);
+		self.nodes.relax(start_index, start_index, 0.0, 0.0);
+		while let Some(current) = self.nodes.pop() {
+			if current.index == goal_index {
+				let tiles = self.nodes.backtrack(cols, start_index, goal_index);
+				return Ok(tiles.windows(2).map(|pair| self.step(pair[0], pair[1])).collect());
+			}
+			for edge in &self.edges[current.index] {
+				self.nodes.relax(edge.to.0 + edge.to.1 * cols,
+				                 current.index,
+				                 current.cost + edge.cost,
+				                 0.0);
+			}
+		}
+		Err(PathError::Unreachable)
+	}
+
+	/// Recover the cheapest edge between two consecutive path tiles
+	fn step(&self, from: (usize, usize), to: (usize, usize)) -> Step {
+		let edge = self.edges(from)
+			.iter()
+			.filter(|edge| edge.to == to)
+			.fold(None, |best: Option<&PlatformEdge>, edge| match best {
+				Some(best) if best.cost <= edge.cost => Some(best),
+				_ => Some(edge),
+			})
+			.unwrap();
+		Step {
+			from,
+			to,
+			action: edge.action,
+		}
+	}
+
+	fn is_solid(&self, x: i64, y: i64) -> bool {
+		x < 0 || y < 0 || x >= self.cols as i64 || y >= self.rows as i64 ||
+		self.solid[x as usize + y as usize * self.cols]
+	}
+
+	/// Find every edge leaving a tile, keeping only the fastest edge to each target
+	fn connect(&self, from: (usize, usize)) -> Vec<PlatformEdge> {
+		let mut edges: Vec<PlatformEdge> = vec![];
+		if !self.is_standable(from) {
+			return edges;
+		}
+		let mut add = |edge: PlatformEdge| {
+			if edge.to == from {
+				return;
+			}
+			if let Some(old) = edges.iter_mut().find(|x| x.to == edge.to) {
+				if edge.cost < old.cost {
+					*old = edge;
+				}
+				return;
+			}
+			edges.push(edge);
+		};
+		let agility = self.agility;
+		let launch = -(2.0 * agility.gravity * agility.jump_height).sqrt();
+		for &direction in &[-1i64, 1] {
+			let side = (from.0 as i64 + direction, from.1 as i64);
+			if side.0 >= 0 && self.is_standable((side.0 as usize, from.1)) {
+				add(PlatformEdge {
+					to: (side.0 as usize, from.1),
+					action: Action::Walk,
+					cost: 1.0 / agility.run_speed,
+				});
+			}
+			let ledge = !self.is_solid(side.0, side.1) && !self.is_solid(side.0, side.1 + 1);
+			for &fraction in &SPEEDS {
+				let speed = direction as f32 * fraction * agility.run_speed;
+				if let Some((to, cost)) = self.trace(from, Vector(speed, launch)) {
+					add(PlatformEdge {
+						to,
+						action: Action::Jump(Vector(speed, launch)),
+						cost,
+					});
+				}
+				if ledge {
+					if let Some((to, cost)) = self.trace(from, Vector(speed, 0.0)) {
+						add(PlatformEdge {
+							to,
+							action: Action::Fall(Vector(speed, 0.0)),
+							cost,
+						});
+					}
+				}
+			}
+		}
+		edges
+	}
+
+	/// Simulate a trajectory and return the tile it lands on and the time it takes
+	///
+	/// The trajectory fails if it hits a wall or a ceiling, or if it does not land
+	/// within `MAX_STEPS` time steps.
+	fn trace(&self, from: (usize, usize), mut velocity: Vector) -> Option<((usize, usize), f32)> {
+		let agility = self.agility;
+		let mut position = Vector(from.0 as f32 + 0.5, from.1 as f32 + 0.5);
+		let mut previous = (from.0 as i32, from.1 as i32);
+		let mut time = 0.0;
+		for _ in 0..MAX_STEPS {
+			if position.1 >= self.rows as f32 + 1.0 {
+				break;
+			}
+			velocity.1 += agility.gravity * agility.time_step;
+			let next = Vector(position.0 + velocity.0 * agility.time_step,
+			                  position.1 + velocity.1 * agility.time_step);
+			time += agility.time_step;
+			for tile in Line(position, next).supercover() {
+				if tile == previous {
+					continue;
+				}
+				if self.is_solid(tile.0 as i64, tile.1 as i64) {
+					let landed = tile == (previous.0, previous.1 + 1) && next.1 > position.1;
+					return if landed {
+						Some(((previous.0 as usize, previous.1 as usize), time))
+					} else {
+						None
+					};
+				}
+				previous = tile;
+			}
+			position = next;
+		}
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn world() -> TileNet<usize> {
+		let mut net: TileNet<usize> = TileNet::new(20, 10);
+		net.set_row(&1, 9);
+		net.set_box(&0, (8, 9), (10, 9));
+		net.set_box(&1, (14, 6), (19, 8));
+		net
+	}
+
+	#[test]
+	fn walk_and_jump() {
+		let net = world();
+		let mut graph = PlatformGraph::new(&net, Agility::default(), |x| *x == 1);
+		assert!(graph.is_standable((0, 8)));
+		assert!(!graph.is_standable((0, 7)));
+		assert!(!graph.is_standable((9, 8)));
+		let path = graph.find((0, 8), (17, 5)).unwrap();
+		assert_eq!(path.first().unwrap().from, (0, 8));
+		assert_eq!(path.last().unwrap().to, (17, 5));
+		for pair in path.windows(2) {
+			assert_eq!(pair[0].to, pair[1].from);
+		}
+		assert!(path.iter().all(|x| graph.is_standable(x.to)));
+		assert!(path.iter().any(|x| matches!(x.action, Action::Jump(_))));
+	}
+
+	#[test]
+	fn fall_off_ledge() {
+		let net = world();
+		let graph = PlatformGraph::new(&net, Agility::default(), |x| *x == 1);
+		assert!(graph.edges((14, 5))
+			.iter()
+			.any(|x| matches!(x.action, Action::Fall(_)) && x.to.1 == 8));
+	}
+
+	#[test]
+	fn too_high() {
+		let net = world();
+		let agility = Agility { jump_height: 1.0, ..Agility::default() };
+		let mut graph = PlatformGraph::new(&net, agility, |x| *x == 1);
+		assert_eq!(graph.find((0, 8), (17, 5)), Err(PathError::Unreachable));
+	}
+
+	#[test]
+	#[should_panic(expected = "the gravity must be positive")]
+	fn zero_gravity() {
+		let agility = Agility { gravity: 0.0, ..Agility::default() };
+		PlatformGraph::new(&world(), agility, |x| *x == 1);
+	}
+
+	#[test]
+	fn slow_fall_is_capped() {
+		let net = world();
+		let graph = PlatformGraph::new(&net, Agility::default(), |x| *x == 1);
+		assert_eq!(graph.trace((2, 2), Vector(0.0, 0.0)).map(|x| x.0), Some((2, 8)));
+		// Landing would take millions of steps, so the trace gives up instead
+		let agility = Agility { gravity: 1e-6, time_step: 1e-3, ..Agility::default() };
+		let graph = PlatformGraph::new(&net, agility, |x| *x == 1);
+		assert_eq!(graph.trace((2, 2), Vector(0.0, 0.0)), None);
+	}
+}
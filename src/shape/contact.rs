@@ -0,0 +1,196 @@
+use super::{Shape, TileNet, Vector};
+use super::convex::scaled;
+use super::gjk::closest;
+
+/// How two shapes lie relative to each other
+///
+/// Created by `Shape::contact`. The first shape is the one the method is called on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+	/// Whether the shapes overlap by more than a touch
+	pub overlap: bool,
+	/// Distance between the shapes, 0 when they overlap
+	pub distance: f32,
+	/// Smallest movement of the first shape that separates it from the second
+	///
+	/// Zero when the shapes do not overlap.
+	pub penetration: Vector,
+	/// The closest points of the first and the second shape
+	///
+	/// When the shapes overlap these are the deepest points instead, which
+	/// coincide after moving the first shape by `penetration`.
+	pub closest: (Vector, Vector),
+}
+
+impl Shape {
+	/// Find the closest points, distance, and penetration between two shapes
+	///
+	/// Uses GJK on the convex cores of the shapes, followed by EPA when the cores
+	/// overlap. The results are accurate to about 1e-5 tiles.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let ball = Shape::Circle { center: Vector(0.0, 0.0), radius: 0.5 };
+	/// let tile = Shape::Aabb { min: Vector(0.0, 0.0), max: Vector(1.0, 1.0) };
+	/// let apart = ball.contact(Vector(-1.0, 0.5), &tile, Vector(0.0, 0.0));
+	/// assert!(!apart.overlap && (apart.distance - 0.5).abs() < 1e-4);
+	/// let inside = ball.contact(Vector(-0.25, 0.5), &tile, Vector(0.0, 0.0));
+	/// assert!(inside.overlap);
+	/// assert!((inside.penetration.0 + 0.25).abs() < 1e-4 && inside.penetration.1.abs() < 1e-4);
+	/// ```
+	pub fn contact(&self, position: Vector, other: &Shape, other_position: Vector) -> Contact {
+		let (core, radius) = self.rounded(position);
+		let (other_core, other_radius) = other.rounded(other_position);
+		let result = closest(&core, &other_core);
+		let first = result.first - scaled(result.normal, radius);
+		let second = result.second + scaled(result.normal, other_radius);
+		let separation = result.separation - radius - other_radius;
+		let overlap = separation < -1e-5;
+		Contact {
+			overlap,
+			distance: separation.max(0.0),
+			penetration: if overlap {
+				scaled(result.normal, -separation)
+			} else {
+				Vector(0.0, 0.0)
+			},
+			closest: (first, second),
+		}
+	}
+}
+
+impl<T> TileNet<T> {
+	/// Find the tiles whose geometry a shape overlaps, deepest first
+	///
+	/// `geometry` gives the shape of a tile relative to its top left corner, or
+	/// `None` for tiles that can not be collided with. Tile shapes must lie within
+	/// their tile. Use `Shape::Aabb` from (0, 0) to (1, 1) for solid squares.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<usize> = TileNet::new(10, 10);
+	/// net.set_row(&1, 5);
+	/// // Solid squares and slopes that rise to the right
+	/// net.set(&2, (4, 4));
+	/// let geometry = |x: &usize| match *x {
+	///   1 => Some(Shape::Aabb { min: Vector(0.0, 0.0), max: Vector(1.0, 1.0) }),
+	///   2 => Some(Shape::Polygon(vec![Vector(0.0, 1.0), Vector(1.0, 0.0), Vector(1.0, 1.0)])),
+	///   _ => None,
+	/// };
+	/// let ball = Shape::Circle { center: Vector(0.0, 0.0), radius: 0.5 };
+	/// let contacts = net.contacts(&ball, Vector(4.5, 4.6), geometry);
+	/// assert_eq!(contacts[0].0, (4, 4));
+	/// assert_eq!(contacts.len(), 2);
+	/// ```
+	pub fn contacts<F>(&self,
+	                   shape: &Shape,
+	                   position: Vector,
+	                   mut geometry: F)
+	                   -> Vec<((i32, i32), Contact)>
+		where F: FnMut(&T) -> Option<Shape>
+	{
+		let mut contacts = vec![];
+		for tile in shape.tiles(position) {
+			if tile.0 < 0 || tile.1 < 0 {
+				continue;
+			}
+			let found = self.get((tile.0 as usize, tile.1 as usize)).and_then(&mut geometry);
+			if let Some(tile_shape) = found {
+				let corner = Vector(tile.0 as f32, tile.1 as f32);
+				let contact = shape.contact(position, &tile_shape, corner);
+				if contact.overlap {
+					contacts.push((tile, contact));
+				}
+			}
+		}
+		contacts.sort_by(|a, b| {
+			b.1.penetration.norm2sq().partial_cmp(&a.1.penetration.norm2sq()).unwrap()
+		});
+		contacts
+	}
+
+	/// Find a small correction that moves a shape out of the tiles it overlaps
+	///
+	/// Repeatedly moves the shape out of its deepest contact, at most `iterations`
+	/// times. Returns the total correction. In a gap that is too narrow for the
+	/// shape, the shape may still overlap a tile afterwards.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<usize> = TileNet::new(10, 10);
+	/// net.set_row(&1, 5);
+	/// let square = |x: &usize| if *x == 1 {
+	///   Some(Shape::Aabb { min: Vector(0.0, 0.0), max: Vector(1.0, 1.0) })
+	/// } else {
+	///   None
+	/// };
+	/// let ball = Shape::Circle { center: Vector(0.0, 0.0), radius: 0.5 };
+	/// let correction = net.push_out(&ball, Vector(3.3, 4.8), 8, square);
+	/// assert!(correction.0.abs() < 1e-4 && (correction.1 + 0.3).abs() < 1e-4);
+	/// ```
+	pub fn push_out<F>(&self,
+	                   shape: &Shape,
+	                   position: Vector,
+	                   iterations: usize,
+	                   mut geometry: F)
+	                   -> Vector
+		where F: FnMut(&T) -> Option<Shape>
+	{
+		let mut correction = Vector(0.0, 0.0);
+		for _ in 0..iterations {
+			match self.contacts(shape, position + correction, &mut geometry).first() {
+				Some(&(_, contact)) => correction += contact.penetration,
+				None => break,
+			}
+		}
+		correction
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn square(x: &usize) -> Option<Shape> {
+		if *x == 1 {
+			Some(Shape::Aabb { min: Vector(0.0, 0.0), max: Vector(1.0, 1.0) })
+		} else {
+			None
+		}
+	}
+
+	#[test]
+	fn rounded_shapes() {
+		let capsule = Shape::Capsule {
+			start: Vector(0.0, 0.0),
+			end: Vector(2.0, 0.0),
+			radius: 0.5,
+		};
+		let ball = Shape::Circle { center: Vector(0.0, 0.0), radius: 0.25 };
+		let apart = capsule.contact(Vector(0.0, 0.0), &ball, Vector(1.0, 2.0));
+		assert!((apart.distance - 1.25).abs() < 1e-4);
+		assert!((apart.closest.0.1 - 0.5).abs() < 1e-4 && (apart.closest.1.1 - 1.75).abs() < 1e-4);
+		let inside = capsule.contact(Vector(0.0, 0.0), &ball, Vector(1.0, 0.5));
+		assert!(inside.overlap);
+		assert!((inside.penetration.1 + 0.25).abs() < 1e-4);
+		// Overlapping cores are separated along the shortest way out
+		let bar = Shape::Aabb { min: Vector(0.0, 0.0), max: Vector(4.0, 0.5) };
+		let deep = bar.contact(Vector(0.0, 0.0), &capsule, Vector(1.0, 0.25));
+		assert!((deep.penetration.1.abs() - 0.75).abs() < 1e-4 && deep.penetration.0.abs() < 1e-4);
+	}
+
+	#[test]
+	fn push_out_of_corner() {
+		let mut net: TileNet<usize> = TileNet::new(10, 10);
+		net.set_row(&1, 5);
+		net.set_col(&1, 5);
+		let block = Shape::Aabb { min: Vector(0.0, 0.0), max: Vector(1.0, 1.0) };
+		let position = Vector(4.2, 4.3);
+		assert_eq!(net.contacts(&block, position, square).len(), 3);
+		let correction = net.push_out(&block, position, 8, square);
+		assert!((correction.0 + 0.2).abs() < 1e-4 && (correction.1 + 0.3).abs() < 1e-4);
+		assert!(net.contacts(&block, position + correction, square).is_empty());
+		// Touching tiles are not contacts
+		assert!(net.contacts(&block, Vector(4.0, 4.0), square).is_empty());
+	}
+}
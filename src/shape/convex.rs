@@ -0,0 +1,119 @@
+use super::super::Vector;
+
+pub fn dot(a: Vector, b: Vector) -> f32 {
+	a.0 * b.0 + a.1 * b.1
+}
+
+pub fn cross(a: Vector, b: Vector) -> f32 {
+	a.0 * b.1 - a.1 * b.0
+}
+
+pub fn scaled(a: Vector, factor: f32) -> Vector {
+	Vector(a.0 * factor, a.1 * factor)
+}
+
+pub fn length(a: Vector) -> f32 {
+	a.norm2sq().sqrt()
+}
+
+/// Convex hull by the monotone chain algorithm, without collinear points
+///
+/// Returns fewer than three points for degenerate inputs.
+pub fn hull(points: &[Vector]) -> Vec<Vector> {
+	let mut sorted = points.to_vec();
+	sorted.sort_by(|a, b| (a.0, a.1).partial_cmp(&(b.0, b.1)).unwrap());
+	sorted.dedup();
+	if sorted.len() < 3 {
+		return sorted;
+	}
+	let mut hull: Vec<Vector> = vec![];
+	for pass in 0..2 {
+		let start = hull.len();
+		for &point in &sorted {
+			while hull.len() >= start + 2 &&
+			      cross(hull[hull.len() - 1] - hull[hull.len() - 2],
+			            point - hull[hull.len() - 2]) <= 0.0 {
+				hull.pop();
+			}
+			hull.push(point);
+		}
+		hull.pop();
+		if pass == 0 {
+			sorted.reverse();
+		}
+	}
+	hull
+}
+
+/// The edges of a convex point set, where a single point is an edge of length 0
+pub fn edges(points: &[Vector]) -> Vec<(Vector, Vector)> {
+	match points.len() {
+		0 => vec![],
+		1 => vec![(points[0], points[0])],
+		2 => vec![(points[0], points[1])],
+		count => (0..count).map(|i| (points[i], points[(i + 1) % count])).collect(),
+	}
+}
+
+/// Check if two convex point sets overlap by the separating axis theorem
+///
+/// With `strict`, sets that only touch do not overlap. A point or a segment has
+/// no inside of its own, so it overlaps strictly when it reaches into the inside
+/// of the other set.
+pub fn overlap(a: &[Vector], b: &[Vector], strict: bool) -> bool {
+	let axes = edges(a)
+		.into_iter()
+		.chain(edges(b))
+		.map(|(from, to)| Vector(from.1 - to.1, to.0 - from.0))
+		.chain(vec![Vector(1.0, 0.0), Vector(0.0, 1.0)]);
+	for axis in axes {
+		if axis == Vector(0.0, 0.0) {
+			continue;
+		}
+		let (a_min, a_max) = project(a, axis);
+		let (b_min, b_max) = project(b, axis);
+		let (low, high) = (a_min.max(b_min), a_max.min(b_max));
+		let within = |min: f32, max: f32, other: (f32, f32)| {
+			min == max && other.0 < min && min < other.1
+		};
+		if high < low ||
+		   strict && high <= low && !within(a_min, a_max, (b_min, b_max)) &&
+		   !within(b_min, b_max, (a_min, a_max)) {
+			return false;
+		}
+	}
+	true
+}
+
+fn project(points: &[Vector], axis: Vector) -> (f32, f32) {
+	points.iter()
+		.map(|p| dot(*p, axis))
+		.fold((f32::INFINITY, f32::NEG_INFINITY), |x, y| (x.0.min(y), x.1.max(y)))
+}
+
+/// Distance from a point to a segment
+pub fn point_segment(p: Vector, a: Vector, b: Vector) -> f32 {
+	let along = b - a;
+	let squared = along.norm2sq();
+	let t = if squared > 0.0 { (dot(p - a, along) / squared).clamp(0.0, 1.0) } else { 0.0 };
+	let mut closest = along;
+	closest.scale(t);
+	length(p - (a + closest))
+}
+
+/// Distance between two convex point sets, which is 0 when they overlap
+pub fn distance(a: &[Vector], b: &[Vector]) -> f32 {
+	if overlap(a, b, false) {
+		return 0.0;
+	}
+	let mut best = f32::INFINITY;
+	for (a0, a1) in edges(a) {
+		for (b0, b1) in edges(b) {
+			best = best.min(point_segment(a0, b0, b1))
+				.min(point_segment(a1, b0, b1))
+				.min(point_segment(b0, a0, a1))
+				.min(point_segment(b1, a0, a1));
+		}
+	}
+	best
+}
@@ -0,0 +1,249 @@
+use super::super::Vector;
+use super::convex::{cross, dot, hull, length, scaled};
+
+const ITERATIONS: usize = 64;
+const TOLERANCE: f32 = 1e-5;
+
+/// A point of the Minkowski difference together with the points it came from
+#[derive(Clone, Copy, Debug)]
+struct Support {
+	point: Vector,
+	a: Vector,
+	b: Vector,
+}
+
+/// Closest features of two convex point sets
+#[derive(Clone, Copy, Debug)]
+pub struct Closest {
+	/// The closest, or when overlapping the deepest, point of the first set
+	pub first: Vector,
+	/// The closest, or when overlapping the deepest, point of the second set
+	pub second: Vector,
+	/// Unit vector pointing from the second set toward the first
+	pub normal: Vector,
+	/// Distance between the sets, negative by the penetration depth when they overlap
+	pub separation: f32,
+}
+
+/// Find the closest points of two convex point sets with GJK, and the
+/// penetration with EPA if they overlap
+pub fn closest(a: &[Vector], b: &[Vector]) -> Closest {
+	let mut simplex = vec![support(a, b, Vector(1.0, 0.0))];
+	let mut weights = vec![1.0];
+	for _ in 0..ITERATIONS {
+		let v = combine(&simplex, &weights, |x| x.point);
+		if v.norm2sq() <= TOLERANCE * TOLERANCE {
+			return penetration(a, b, simplex);
+		}
+		let next = support(a, b, scaled(v, -1.0));
+		let converged = simplex.iter().any(|x| x.point == next.point) ||
+		                dot(v, v) - dot(next.point, v) <= TOLERANCE * dot(v, v).sqrt();
+		if converged {
+			break;
+		}
+		simplex.push(next);
+		match nearest(&simplex) {
+			Some((reduced, lambdas)) => {
+				simplex = reduced;
+				weights = lambdas;
+			}
+			None => return penetration(a, b, simplex),
+		}
+	}
+	let first = combine(&simplex, &weights, |x| x.a);
+	let second = combine(&simplex, &weights, |x| x.b);
+	let separation = length(first - second);
+	Closest {
+		first,
+		second,
+		normal: scaled(first - second, 1.0 / separation),
+		separation,
+	}
+}
+
+/// The point of the Minkowski difference furthest along a direction
+fn support(a: &[Vector], b: &[Vector], direction: Vector) -> Support {
+	let furthest = |points: &[Vector], direction: Vector| {
+		*points.iter()
+			.max_by(|x, y| dot(**x, direction).partial_cmp(&dot(**y, direction)).unwrap())
+			.unwrap()
+	};
+	let (a, b) = (furthest(a, direction), furthest(b, scaled(direction, -1.0)));
+	Support {
+		point: a - b,
+		a,
+		b,
+	}
+}
+
+fn combine<F>(simplex: &[Support], weights: &[f32], part: F) -> Vector
+	where F: Fn(&Support) -> Vector
+{
+	simplex.iter()
+		.zip(weights)
+		.fold(Vector(0.0, 0.0), |sum, (x, weight)| sum + scaled(part(x), *weight))
+}
+
+/// Reduce a simplex to the part nearest to the origin, with the barycentric weights
+/// of the nearest point
+///
+/// Returns `None` if the simplex is a triangle that contains the origin.
+fn nearest(simplex: &[Support]) -> Option<(Vec<Support>, Vec<f32>)> {
+	match *simplex {
+		[p] => Some((vec![p], vec![1.0])),
+		[p, q] => Some(segment(p, q)),
+		[p, q, r] => {
+			let area = cross(q.point - p.point, r.point - p.point);
+			let sides = [cross(q.point - p.point, scaled(p.point, -1.0)),
+			             cross(r.point - q.point, scaled(q.point, -1.0)),
+			             cross(p.point - r.point, scaled(r.point, -1.0))];
+			let inside = area.abs() > TOLERANCE * TOLERANCE &&
+			             (sides.iter().all(|x| *x >= 0.0) || sides.iter().all(|x| *x <= 0.0));
+			if inside {
+				return None;
+			}
+			[segment(p, q), segment(q, r), segment(p, r)]
+				.iter()
+				.cloned()
+				.min_by(|x, y| {
+					let x = combine(&x.0, &x.1, |s| s.point).norm2sq();
+					let y = combine(&y.0, &y.1, |s| s.point).norm2sq();
+					x.partial_cmp(&y).unwrap()
+				})
+		}
+		_ => unreachable!(),
+	}
+}
+
+fn segment(p: Support, q: Support) -> (Vec<Support>, Vec<f32>) {
+	let along = q.point - p.point;
+	let squared = along.norm2sq();
+	let t = if squared > 0.0 { -dot(p.point, along) / squared } else { 0.0 };
+	if t <= 0.0 {
+		(vec![p], vec![1.0])
+	} else if t >= 1.0 {
+		(vec![q], vec![1.0])
+	} else {
+		(vec![p, q], vec![1.0 - t, t])
+	}
+}
+
+/// Expand a simplex that contains the origin to the edge of the Minkowski
+/// difference that is closest to the origin
+fn penetration(a: &[Vector], b: &[Vector], simplex: Vec<Support>) -> Closest {
+	let mut candidates = simplex.clone();
+	let mut directions =
+		vec![Vector(1.0, 0.0), Vector(-1.0, 0.0), Vector(0.0, 1.0), Vector(0.0, -1.0)];
+	if let [p, q] = *simplex {
+		let along = q.point - p.point;
+		directions.push(Vector(along.1, -along.0));
+		directions.push(Vector(-along.1, along.0));
+	}
+	candidates.extend(directions.into_iter().map(|x| support(a, b, x)));
+	let points: Vec<Vector> = candidates.iter().map(|x| x.point).collect();
+	let mut polytope: Vec<Support> = hull(&points)
+		.into_iter()
+		.map(|point| *candidates.iter().find(|x| x.point == point).unwrap())
+		.collect();
+
+	if polytope.len() < 3 {
+		// The difference has no area, so the sets only touch; prefer pushing upward
+		let (p, q) = (polytope[0], *polytope.last().unwrap());
+		let along = q.point - p.point;
+		let (parts, lambdas) = segment(p, q);
+		let normal = if along.norm2sq() > 0.0 {
+			scaled(Vector(along.1, -along.0), 1.0 / length(along))
+		} else {
+			Vector(0.0, -1.0)
+		};
+		let normal = if normal.1 > 0.0 || normal.1 == 0.0 && normal.0 > 0.0 {
+			scaled(normal, -1.0)
+		} else {
+			normal
+		};
+		return Closest {
+			first: combine(&parts, &lambdas, |x| x.a),
+			second: combine(&parts, &lambdas, |x| x.b),
+			normal,
+			separation: 0.0,
+		};
+	}
+
+	let mut best = (0, Vector(0.0, 0.0), 0.0);
+	for _ in 0..ITERATIONS {
+		let count = polytope.len() as f32;
+		let center = polytope.iter()
+			.fold(Vector(0.0, 0.0), |sum, x| sum + scaled(x.point, 1.0 / count));
+		best = (0, Vector(0.0, 0.0), f32::INFINITY);
+		for (i, p) in polytope.iter().enumerate() {
+			let q = polytope[(i + 1) % polytope.len()];
+			let along = q.point - p.point;
+			let mut normal = scaled(Vector(along.1, -along.0), 1.0 / length(along));
+			if dot(normal, center - p.point) > 0.0 {
+				normal = scaled(normal, -1.0);
+			}
+			let distance = dot(normal, p.point);
+			if distance < best.2 {
+				best = (i, normal, distance);
+			}
+		}
+		let (i, normal, distance) = best;
+		let next = support(a, b, normal);
+		if dot(next.point, normal) - distance <= TOLERANCE ||
+		   polytope.iter().any(|x| x.point == next.point) {
+			break;
+		}
+		polytope.insert(i + 1, next);
+	}
+
+	let (i, normal, distance) = best;
+	let (p, q) = (polytope[i], polytope[(i + 1) % polytope.len()]);
+	let along = q.point - p.point;
+	let t = (dot(scaled(normal, distance) - p.point, along) / along.norm2sq()).clamp(0.0, 1.0);
+	Closest {
+		first: p.a + scaled(q.a - p.a, t),
+		second: p.b + scaled(q.b - p.b, t),
+		normal: scaled(normal, -1.0),
+		separation: -distance,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn square(x: f32, y: f32) -> Vec<Vector> {
+		vec![Vector(x, y), Vector(x + 1.0, y), Vector(x + 1.0, y + 1.0), Vector(x, y + 1.0)]
+	}
+
+	#[test]
+	fn separated_squares() {
+		let result = closest(&square(0.0, 0.0), &square(3.0, 0.5));
+		assert!((result.separation - 2.0).abs() < 1e-4);
+		assert_eq!(result.normal, Vector(-1.0, 0.0));
+		assert!((result.first.0 - 1.0).abs() < 1e-4 && (result.second.0 - 3.0).abs() < 1e-4);
+		let diagonal = closest(&[Vector(0.0, 0.0)], &square(3.0, 4.0));
+		assert!((diagonal.separation - 5.0).abs() < 1e-4);
+		assert_eq!(diagonal.second, Vector(3.0, 4.0));
+	}
+
+	#[test]
+	fn overlapping_squares() {
+		let result = closest(&square(0.0, 0.0), &square(0.75, 0.25));
+		assert!((result.separation + 0.25).abs() < 1e-4);
+		assert!((result.normal.0 + 1.0).abs() < 1e-4 && result.normal.1.abs() < 1e-4);
+		assert!((result.first.0 - 1.0).abs() < 1e-4 && (result.second.0 - 0.75).abs() < 1e-4);
+		let touching = closest(&square(0.0, 0.0), &square(1.0, 0.0));
+		assert!(touching.separation.abs() < 1e-4);
+	}
+
+	#[test]
+	fn degenerate_difference() {
+		let result = closest(&[Vector(2.0, 2.0)], &[Vector(2.0, 2.0)]);
+		assert_eq!(result.separation, 0.0);
+		assert_eq!(result.normal, Vector(0.0, -1.0));
+		let result = closest(&[Vector(0.0, 1.0), Vector(4.0, 1.0)], &[Vector(2.0, 1.0)]);
+		assert_eq!(result.separation, 0.0);
+		assert_eq!(result.first, Vector(2.0, 1.0));
+	}
+}
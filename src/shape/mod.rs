@@ -0,0 +1,339 @@
+use std::f32::consts::PI;
+use std::vec;
+use super::{Points, TileNet, TileSet, Vector};
+use self::convex::{cross, distance, dot, hull, length, overlap};
+
+pub use self::contact::Contact;
+pub use self::prune::{Body, Impact, SweepAndPrune};
+
+mod contact;
+mod convex;
+mod gjk;
+mod prune;
+
+/// Convex collision shape
+///
+/// Coordinates are relative to the position of the object, which is given
+/// separately to every query. Every shape is handled as a convex polygon with
+/// a rounded border, so a circle is a point with a radius and a capsule is a
+/// segment with a radius.
+///
+/// ```
+/// use tile_net::*;
+/// let circle = Shape::Circle { center: Vector(0.0, 0.0), radius: 1.2 };
+/// let tiles = circle.tiles(Vector(5.5, 5.5));
+/// assert_eq!(tiles.len(), 9);
+/// assert!(tiles.contains(&(4, 4)));
+/// assert!(!tiles.contains(&(3, 5)));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+	/// Axis-aligned box from `min` to `max`
+	Aabb {
+		/// The corner with the smallest coordinates
+		min: Vector,
+		/// The corner with the largest coordinates
+		max: Vector,
+	},
+	/// Disk around a center
+	Circle {
+		/// The center
+		center: Vector,
+		/// The radius
+		radius: f32,
+	},
+	/// All points within a radius of a segment
+	Capsule {
+		/// One end of the segment
+		start: Vector,
+		/// The other end of the segment
+		end: Vector,
+		/// The radius
+		radius: f32,
+	},
+	/// Convex polygon given by at least three vertices in any order
+	///
+	/// Vertices inside the convex hull of the others are ignored. Fewer vertices
+	/// make a segment or a point, and a polygon without vertices panics when used.
+	Polygon(Vec<Vector>),
+}
+
+impl Shape {
+	/// Get the smallest and largest corner of the bounding box
+	pub fn bounds(&self, position: Vector) -> (Vector, Vector) {
+		let (core, radius) = self.rounded(position);
+		let (mut min, mut max) = (core[0], core[0]);
+		for point in &core {
+			min = Vector(min.0.min(point.0), min.1.min(point.1));
+			max = Vector(max.0.max(point.0), max.1.max(point.1));
+		}
+		(Vector(min.0 - radius, min.1 - radius), Vector(max.0 + radius, max.1 + radius))
+	}
+
+	/// Check if a point lies inside the shape or on its border
+	pub fn contains(&self, position: Vector, point: Vector) -> bool {
+		let (core, radius) = self.rounded(position);
+		if radius > 0.0 {
+			distance(&core, &[point]) <= radius
+		} else {
+			overlap(&core, &[point], false)
+		}
+	}
+
+	/// Check if the shape overlaps the inside of a tile
+	///
+	/// Shapes that only touch the border of a tile do not overlap it.
+	pub fn overlaps_tile(&self, position: Vector, tile: (i32, i32)) -> bool {
+		let (core, radius) = self.rounded(position);
+		touches(&core, radius, tile)
+	}
+
+	/// Get every tile the shape overlaps
+	pub fn tiles(&self, position: Vector) -> Vec<(i32, i32)> {
+		self.sweep(position, Vector(0.0, 0.0))
+	}
+
+	/// Get every tile the shape overlaps while moving along a vector
+	///
+	/// The tiles are sorted by how far along the movement they lie, so the tiles
+	/// the shape runs into first come first.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let square = Shape::Aabb { min: Vector(0.0, 0.0), max: Vector(2.0, 2.0) };
+	/// let tiles = square.sweep(Vector(0.0, 0.0), Vector(3.0, 0.0));
+	/// assert_eq!(tiles.len(), 10);
+	/// assert_eq!(tiles.last().map(|x| x.0), Some(4));
+	/// ```
+	pub fn sweep(&self, position: Vector, movement: Vector) -> Vec<(i32, i32)> {
+		let (core, radius) = self.rounded(position);
+		let moved: Vec<Vector> = core.iter().map(|x| *x + movement).collect();
+		let swept = hull(&core.iter().chain(moved.iter()).cloned().collect::<Vec<_>>());
+		let (min, max) = self.bounds(position);
+		let (min, max) = (Vector(min.0.min(min.0 + movement.0), min.1.min(min.1 + movement.1)),
+		                  Vector(max.0.max(max.0 + movement.0), max.1.max(max.1 + movement.1)));
+		let mut tiles = vec![];
+		for y in min.1.floor() as i32..max.1.ceil() as i32 {
+			for x in min.0.floor() as i32..max.0.ceil() as i32 {
+				if touches(&swept, radius, (x, y)) {
+					tiles.push((x, y));
+				}
+			}
+		}
+		let start = Vector((min.0 + max.0) / 2.0, (min.1 + max.1) / 2.0);
+		let along = |tile: &(i32, i32)| {
+			dot(Vector(tile.0 as f32 + 0.5, tile.1 as f32 + 0.5) - start, movement)
+		};
+		tiles.sort_by(|a, b| along(a).partial_cmp(&along(b)).unwrap());
+		tiles
+	}
+
+	/// Get points along the border of the shape, at most one tile apart
+	///
+	/// Use these as the points of a `Collable` so its vertex lines can not pass
+	/// around a tile.
+	pub fn sample_points(&self) -> Vec<(f32, f32)> {
+		let (core, radius) = self.rounded(Vector(0.0, 0.0));
+		let mut points = vec![];
+		if radius > 0.0 {
+			let count = ((2.0 * PI * radius).ceil() as usize).max(8);
+			for &vertex in &core {
+				for i in 0..count {
+					let angle = 2.0 * PI * i as f32 / count as f32;
+					let point = vertex + Vector(radius * angle.cos(), radius * angle.sin());
+					if distance(&core, &[point]) >= radius * 0.999 {
+						points.push(point);
+					}
+				}
+			}
+		}
+		let edges = if core.len() == 2 {
+			vec![(core[0], core[1]), (core[1], core[0])]
+		} else {
+			convex::edges(&core)
+		};
+		let clockwise = core.len() > 2 && cross(core[1] - core[0], core[2] - core[1]) < 0.0;
+		for (from, to) in edges {
+			let along = to - from;
+			let norm = length(along);
+			if norm == 0.0 {
+				continue;
+			}
+			let mut normal = if clockwise {
+				Vector(along.1, -along.0)
+			} else {
+				Vector(-along.1, along.0)
+			};
+			normal.scale(-radius / norm);
+			// Rounded shapes do not share the ends of their edges with the next edge
+			let steps = norm.ceil() as usize;
+			let last = if radius > 0.0 { steps + 1 } else { steps };
+			for step in 0..last {
+				let mut offset = along;
+				offset.scale(step as f32 / steps as f32);
+				points.push(from + offset + normal);
+			}
+		}
+		// A point has no border, so it is its own sample
+		if points.is_empty() {
+			points.extend(core.first());
+		}
+		points.into_iter().map(|x| (x.0, x.1)).collect()
+	}
+
+	/// Get the sample points of the shape as a `Points` iterator
+	pub fn points(&self, position: Vector) -> Points<'static> {
+		Points::owned(position, self.sample_points())
+	}
+
+	/// The convex core and the radius around it
+	fn rounded(&self, position: Vector) -> (Vec<Vector>, f32) {
+		match *self {
+			Shape::Aabb { min, max } => {
+				(vec![min + position,
+				      Vector(max.0, min.1) + position,
+				      max + position,
+				      Vector(min.0, max.1) + position],
+				 0.0)
+			}
+			Shape::Circle { center, radius } => (vec![center + position], radius),
+			Shape::Capsule { start, end, radius } => {
+				(vec![start + position, end + position], radius)
+			}
+			Shape::Polygon(ref points) => {
+				assert!(!points.is_empty(), "a polygon needs at least one vertex");
+				(hull(&points.iter().map(|x| *x + position).collect::<Vec<_>>()), 0.0)
+			}
+		}
+	}
+}
+
+/// Check if a rounded convex set overlaps the inside of a tile
+fn touches(core: &[Vector], radius: f32, tile: (i32, i32)) -> bool {
+	let (x, y) = (tile.0 as f32, tile.1 as f32);
+	let square = [Vector(x, y), Vector(x + 1.0, y), Vector(x + 1.0, y + 1.0), Vector(x, y + 1.0)];
+	if radius > 0.0 {
+		distance(core, &square) < radius
+	} else {
+		overlap(core, &square, true)
+	}
+}
+
+impl<T> TileNet<T> {
+	/// Get the tiles a shape overlaps while moving along a vector
+	///
+	/// Works like `collide_set`, with the tiles from `Shape::sweep`.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<usize> = TileNet::new(10, 10);
+	/// net.set(&1, (6, 5));
+	/// let ball = Shape::Circle { center: Vector(0.0, 0.0), radius: 0.5 };
+	/// assert!(net.collide_shape(&ball, Vector(1.5, 5.5), Vector(8.0, 0.0)).any(|x| *x == 1));
+	/// assert!(!net.collide_shape(&ball, Vector(1.5, 4.5), Vector(8.0, 0.0)).any(|x| *x == 1));
+	/// ```
+	pub fn collide_shape(&self,
+	                     shape: &Shape,
+	                     position: Vector,
+	                     movement: Vector)
+	                     -> TileSet<'_, T, vec::IntoIter<(i32, i32)>> {
+		self.collide_set(shape.sweep(position, movement).into_iter())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn exact_coverage() {
+		let square = Shape::Aabb { min: Vector(0.0, 0.0), max: Vector(1.0, 1.0) };
+		assert_eq!(square.tiles(Vector(3.0, 3.0)), vec![(3, 3)]);
+		assert_eq!(square.tiles(Vector(3.5, 3.0)), vec![(3, 3), (4, 3)]);
+		let capsule = Shape::Capsule {
+			start: Vector(0.0, 0.0),
+			end: Vector(4.0, 0.0),
+			radius: 0.25,
+		};
+		assert_eq!(capsule.tiles(Vector(1.5, 1.5)).len(), 5);
+		let triangle = Shape::Polygon(vec![Vector(0.0, 0.0), Vector(3.0, 0.0), Vector(0.0, 3.0)]);
+		let tiles = triangle.tiles(Vector(0.0, 0.0));
+		assert_eq!(tiles.len(), 6);
+		assert!(!tiles.contains(&(2, 2)));
+		assert!(!tiles.contains(&(1, 2)));
+	}
+
+	#[test]
+	fn degenerate_polygons() {
+		let segment = Shape::Polygon(vec![Vector(1.0, 1.0), Vector(3.0, 2.0)]);
+		assert_eq!(segment.bounds(Vector(0.0, 0.0)), (Vector(1.0, 1.0), Vector(3.0, 2.0)));
+		assert_eq!(segment.tiles(Vector(0.0, 0.0)), vec![(1, 1), (2, 1)]);
+		let flat = Shape::Polygon(vec![Vector(0.0, 0.0), Vector(2.5, 0.0)]);
+		assert_eq!(flat.tiles(Vector(1.0, 1.5)), vec![(1, 1), (2, 1), (3, 1)]);
+		// Lying on the border between two rows only touches them
+		assert_eq!(flat.tiles(Vector(1.0, 1.0)), vec![]);
+		let mut net: TileNet<usize> = TileNet::new(5, 5);
+		net.set(&1, (2, 1));
+		assert!(net.collide_shape(&flat, Vector(1.0, 1.5), Vector(0.0, 0.0)).any(|x| *x == 1));
+
+		let point = Shape::Polygon(vec![Vector(0.0, 0.0)]);
+		let dot = Shape::Circle { center: Vector(0.0, 0.0), radius: 0.0 };
+		for shape in &[point, dot] {
+			assert_eq!(shape.tiles(Vector(2.5, 1.5)), vec![(2, 1)]);
+			assert_eq!(shape.tiles(Vector(2.0, 1.5)), vec![]);
+			assert_eq!(shape.sample_points(), vec![(0.0, 0.0)]);
+			assert!(shape.sweep(Vector(0.5, 0.5), Vector(2.0, 0.0)).contains(&(1, 0)));
+		}
+	}
+
+	#[test]
+	#[should_panic(expected = "a polygon needs at least one vertex")]
+	fn empty_polygon() {
+		Shape::Polygon(vec![]).bounds(Vector(0.0, 0.0));
+	}
+
+	#[test]
+	fn samples_on_border() {
+		let shapes = vec![Shape::Aabb { min: Vector(-1.0, -1.0), max: Vector(2.0, 1.5) },
+		                  Shape::Circle { center: Vector(1.0, 1.0), radius: 2.5 },
+		                  Shape::Capsule {
+			                  start: Vector(0.0, 0.0),
+			                  end: Vector(3.0, 4.0),
+			                  radius: 0.75,
+		                  },
+		                  Shape::Polygon(vec![Vector(0.0, 0.0),
+		                                      Vector(4.0, 1.0),
+		                                      Vector(3.0, 3.0),
+		                                      Vector(1.0, 2.0)])];
+		for shape in shapes {
+			let points = shape.sample_points();
+			for point in &points {
+				let point = Vector::from_tuple(*point);
+				let (core, radius) = shape.rounded(Vector(0.0, 0.0));
+				assert!((distance(&core, &[point]) - radius).abs() < 1e-4);
+				let (min, max) = shape.bounds(Vector(0.0, 0.0));
+				assert!(point.0 >= min.0 - 1e-4 && point.0 <= max.0 + 1e-4);
+				assert!(point.1 >= min.1 - 1e-4 && point.1 <= max.1 + 1e-4);
+			}
+			// Every border point is within half a tile of a sample
+			let (min, max) = shape.bounds(Vector(0.0, 0.0));
+			let step = 0.05;
+			let mut y = min.1;
+			while y <= max.1 {
+				let mut x = min.0;
+				while x <= max.0 {
+					let inside = shape.contains(Vector(0.0, 0.0), Vector(x, y));
+					let near_outside = !shape.contains(Vector(0.0, 0.0), Vector(x + step, y)) ||
+					                   !shape.contains(Vector(0.0, 0.0), Vector(x - step, y));
+					if inside && near_outside {
+						assert!(points.iter().any(|p| {
+							length(Vector::from_tuple(*p) - Vector(x, y)) <= 0.5 + step
+						}), "{:?} {} {}", shape, x, y);
+					}
+					x += step;
+				}
+				y += step;
+			}
+		}
+	}
+}
@@ -0,0 +1,285 @@
+use std::cmp::Ordering;
+use super::{Shape, Vector};
+use super::convex::{dot, length, scaled};
+
+/// The most advancement steps taken by `Body::time_of_impact`
+const ITERATIONS: usize = 64;
+
+/// A shape that moves during a step
+#[derive(Clone, Debug, PartialEq)]
+pub struct Body {
+	/// The shape of the body
+	pub shape: Shape,
+	/// The position at the start of the step
+	pub position: Vector,
+	/// The movement during the step
+	pub movement: Vector,
+}
+
+impl Body {
+	/// Get the smallest and largest corner of the box covering the whole movement
+	pub fn swept_bounds(&self) -> (Vector, Vector) {
+		let (min, max) = self.shape.bounds(self.position);
+		let (end_min, end_max) = self.shape.bounds(self.position + self.movement);
+		(Vector(min.0.min(end_min.0), min.1.min(end_min.1)),
+		 Vector(max.0.max(end_max.0), max.1.max(end_max.1)))
+	}
+
+	/// Find the fraction of the step at which two bodies first touch
+	///
+	/// Uses conservative advancement: both bodies are moved forward by the time
+	/// the distance between them takes to close at their current closing speed,
+	/// until they are within `tolerance` tiles of each other. Returns `None` if
+	/// they do not come that close during the step, and 0 if they already overlap.
+	/// The advancement also returns `None` if it has not come within `tolerance`
+	/// after 64 steps.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let ball = Shape::Circle { center: Vector(0.0, 0.0), radius: 0.5 };
+	/// let left = Body {
+	///   shape: ball.clone(),
+	///   position: Vector(0.0, 0.0),
+	///   movement: Vector(4.0, 0.0),
+	/// };
+	/// let right = Body {
+	///   shape: ball,
+	///   position: Vector(5.0, 0.0),
+	///   movement: Vector(-4.0, 0.0),
+	/// };
+	/// let time = left.time_of_impact(&right, 0.001).unwrap();
+	/// assert!((time - 0.5).abs() < 0.001);
+	/// ```
+	pub fn time_of_impact(&self, other: &Body, tolerance: f32) -> Option<f32> {
+		assert!(tolerance > 0.0, "the tolerance must be positive");
+		let relative = self.movement - other.movement;
+		let mut time = 0.0;
+		for _ in 0..ITERATIONS {
+			let contact = self.contact_at(other, time);
+			if contact.overlap || contact.distance <= tolerance {
+				return Some(time);
+			}
+			let toward = scaled(contact.closest.1 - contact.closest.0, 1.0 / contact.distance);
+			let closing = dot(relative, toward);
+			if closing <= 0.0 {
+				return None;
+			}
+			// Stop just short of touching so the bodies are never left overlapping
+			time += (contact.distance - tolerance / 2.0) / closing;
+			if time > 1.0 {
+				return None;
+			}
+		}
+		None
+	}
+
+	fn at(&self, time: f32) -> Vector {
+		self.position + scaled(self.movement, time)
+	}
+
+	fn contact_at(&self, other: &Body, time: f32) -> super::Contact {
+		self.shape.contact(self.at(time), &other.shape, other.at(time))
+	}
+}
+
+/// Two bodies that touch during a step
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Impact {
+	/// Index of the first body, which is smaller than `second`
+	pub first: usize,
+	/// Index of the second body
+	pub second: usize,
+	/// Fraction of the step at which the bodies touch
+	pub time: f32,
+	/// Unit vector pointing from the second body toward the first at the time of impact
+	pub normal: Vector,
+}
+
+/// Broad phase for many moving bodies
+///
+/// Sorts the swept bounding boxes of the bodies along the x axis and only pairs
+/// up boxes that overlap. The order is kept between calls, so when the bodies
+/// are given in the same order every step, sorting them again is cheap.
+///
+/// ```
+/// use tile_net::*;
+/// let ball = Shape::Circle { center: Vector(0.0, 0.0), radius: 0.25 };
+/// let bodies = vec![
+///   Body { shape: ball.clone(), position: Vector(1.0, 1.0), movement: Vector(4.0, 0.0) },
+///   Body { shape: ball.clone(), position: Vector(9.0, 1.0), movement: Vector(0.0, 0.0) },
+///   Body { shape: ball, position: Vector(3.0, 3.0), movement: Vector(0.0, -4.0) },
+/// ];
+/// let mut prune = SweepAndPrune::new();
+/// assert_eq!(prune.pairs(&bodies), vec![(0, 2)]);
+/// let impacts = prune.impacts(&bodies, 0.001);
+/// assert_eq!(impacts.len(), 1);
+/// assert!(impacts[0].time > 0.0 && impacts[0].time < 1.0);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SweepAndPrune {
+	order: Vec<usize>,
+}
+
+impl SweepAndPrune {
+	/// Create a broad phase without any bodies
+	pub fn new() -> SweepAndPrune {
+		SweepAndPrune { order: vec![] }
+	}
+
+	/// Find the pairs of bodies whose swept bounding boxes overlap
+	///
+	/// Boxes that only touch count as overlapping. Each pair is listed once with
+	/// the smaller index first, and the pairs are sorted.
+	pub fn pairs(&mut self, bodies: &[Body]) -> Vec<(usize, usize)> {
+		let bounds: Vec<(Vector, Vector)> = bodies.iter().map(Body::swept_bounds).collect();
+		// The order holds every index below its length, so only new bodies are added
+		self.order.retain(|x| *x < bodies.len());
+		let known = self.order.len();
+		self.order.extend(known..bodies.len());
+		// The stable sort merges runs that are already sorted, so it is fast for the
+		// nearly sorted order of the last step
+		let left = |index: usize| bounds[index].0 .0;
+		self.order.sort_by(|a, b| left(*a).partial_cmp(&left(*b)).unwrap_or(Ordering::Equal));
+
+		let mut pairs = vec![];
+		let mut active: Vec<usize> = vec![];
+		for &index in &self.order {
+			let (min, max) = bounds[index];
+			active.retain(|x| bounds[*x].1 .0 >= min.0);
+			for &other in &active {
+				let (other_min, other_max) = bounds[other];
+				if other_min.1 <= max.1 && min.1 <= other_max.1 {
+					pairs.push((index.min(other), index.max(other)));
+				}
+			}
+			active.push(index);
+		}
+		pairs.sort_unstable();
+		pairs
+	}
+
+	/// Find the pairs of bodies that touch during the step, earliest first
+	///
+	/// Every pair from `pairs` is checked with `Body::time_of_impact`.
+	pub fn impacts(&mut self, bodies: &[Body], tolerance: f32) -> Vec<Impact> {
+		let mut impacts = vec![];
+		for (first, second) in self.pairs(bodies) {
+			let (a, b) = (&bodies[first], &bodies[second]);
+			if let Some(time) = a.time_of_impact(b, tolerance) {
+				let contact = a.contact_at(b, time);
+				let away = contact.closest.0 - contact.closest.1;
+				let normal = if contact.overlap {
+					scaled(contact.penetration, 1.0 / length(contact.penetration))
+				} else if contact.distance > 0.0 {
+					scaled(away, 1.0 / contact.distance)
+				} else {
+					Vector(0.0, 0.0)
+				};
+				impacts.push(Impact {
+					first,
+					second,
+					time,
+					normal,
+				});
+			}
+		}
+		impacts.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+		impacts
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn pairs_match_brute_force() {
+		let mut seed = 11u32;
+		let mut random = move || {
+			seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+			(seed >> 16) as f32 / 65536.0
+		};
+		let mut prune = SweepAndPrune::new();
+		let mut bodies = vec![];
+		for _ in 0..40 {
+			bodies.push(Body {
+				shape: Shape::Circle {
+					center: Vector(0.0, 0.0),
+					radius: 0.2 + random(),
+				},
+				position: Vector(random() * 30.0, random() * 30.0),
+				movement: Vector(random() * 4.0 - 2.0, random() * 4.0 - 2.0),
+			});
+		}
+		for _ in 0..5 {
+			let mut expected = vec![];
+			for i in 0..bodies.len() {
+				for j in i + 1..bodies.len() {
+					let (a_min, a_max) = bodies[i].swept_bounds();
+					let (b_min, b_max) = bodies[j].swept_bounds();
+					if a_min.0 <= b_max.0 && b_min.0 <= a_max.0 && a_min.1 <= b_max.1 &&
+					   b_min.1 <= a_max.1 {
+						expected.push((i, j));
+					}
+				}
+			}
+			assert_eq!(prune.pairs(&bodies), expected);
+			for body in &mut bodies {
+				body.position += body.movement;
+			}
+			bodies.truncate(bodies.len() - 5);
+		}
+	}
+
+	#[test]
+	fn bodies_added_and_removed() {
+		let body = |x: f32| {
+			Body {
+				shape: Shape::Circle {
+					center: Vector(0.0, 0.0),
+					radius: 0.5,
+				},
+				position: Vector(x, 0.0),
+				movement: Vector(0.0, 0.0),
+			}
+		};
+		let mut prune = SweepAndPrune::new();
+		assert_eq!(prune.pairs(&[body(4.0), body(0.0)]), vec![]);
+		let bodies = [body(4.0), body(0.0), body(4.5), body(0.75)];
+		assert_eq!(prune.pairs(&bodies), vec![(0, 2), (1, 3)]);
+		assert_eq!(prune.pairs(&bodies[..3]), vec![(0, 2)]);
+		assert_eq!(prune.order.len(), 3);
+	}
+
+	#[test]
+	fn fast_bodies_do_not_tunnel() {
+		let bullet = Body {
+			shape: Shape::Circle {
+				center: Vector(0.0, 0.0),
+				radius: 0.1,
+			},
+			position: Vector(0.0, 0.5),
+			movement: Vector(20.0, 0.0),
+		};
+		let door = Body {
+			shape: Shape::Aabb {
+				min: Vector(0.0, 0.0),
+				max: Vector(0.2, 3.0),
+			},
+			position: Vector(10.0, -1.0),
+			movement: Vector(0.0, 0.5),
+		};
+		// Neither the start nor the end poses overlap
+		assert!(!bullet.shape.contact(bullet.position, &door.shape, door.position).overlap);
+		let time = bullet.time_of_impact(&door, 0.001).unwrap();
+		assert!((bullet.at(time).0 - 9.9).abs() < 0.01);
+		let impacts = SweepAndPrune::new().impacts(&[door.clone(), bullet.clone()], 0.001);
+		assert_eq!(impacts.len(), 1);
+		assert_eq!((impacts[0].first, impacts[0].second), (0, 1));
+		assert!((impacts[0].normal.0 - 1.0).abs() < 1e-3);
+		// Bodies moving apart never touch
+		let mut away = bullet;
+		away.movement = Vector(-20.0, 0.0);
+		assert_eq!(away.time_of_impact(&door, 0.001), None);
+	}
+}
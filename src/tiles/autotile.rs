@@ -0,0 +1,240 @@
+use super::{Span, TileNet};
+
+/// How an `Autotiler` picks the variant of a tile
+///
+/// Neighbours outside of the net count as having the same terrain as the tile itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Rule {
+	/// The variant is a 4-bit mask of the orthogonal neighbours with the same terrain
+	///
+	/// North is 1, east is 2, south is 4, and west is 8.
+	FourBit,
+	/// The variant is one of the 47 blob tiles, from an 8-bit mask of all neighbours
+	///
+	/// Clockwise from north the bits are 1, 2, 4, 8, 16, 32, 64, and 128. A corner
+	/// only counts when both neighbours next to it match as well, which leaves 47
+	/// distinct masks. The variant is the position of the mask among them in
+	/// ascending order, so 0 is an isolated tile and 46 is a fully surrounded one.
+	Blob,
+	/// The variant is taken from the first matching tile of a Wang tile set
+	Wang {
+		/// The tiles, tried in order
+		tiles: Vec<WangTile>,
+		/// The variant written for tiles that no Wang tile matches
+		fallback: usize,
+	},
+}
+
+/// Wang tile given as data
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WangTile {
+	/// The terrain of the tile itself
+	pub terrain: u32,
+	/// The terrain of the north, east, south, and west neighbours, where `None` matches any
+	pub edges: [Option<u32>; 4],
+	/// The variant written for a match
+	pub variant: usize,
+}
+
+/// Picks visual variants of tiles from their neighbours
+///
+/// The terrain closure maps every tile to a terrain. Neighbours connect when their
+/// terrains are equal.
+///
+/// ```
+/// use tile_net::*;
+/// let mut net: TileNet<usize> = TileNet::new(5, 5);
+/// net.set_row(&1, 2);
+/// net.set_col(&1, 2);
+/// let variants = Autotiler::new(Rule::FourBit).apply(&net, |x| *x as u32);
+/// assert_eq!(variants.get((2, 2)), Some(&15));
+/// assert_eq!(variants.get((1, 2)), Some(&10));
+/// assert_eq!(variants.get((2, 1)), Some(&5));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Autotiler {
+	rule: Rule,
+	blob: Vec<usize>,
+}
+
+/// Offsets of the neighbours, clockwise from north
+static AROUND: [(i64, i64); 8] =
+	[(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+impl Autotiler {
+	/// Create an autotiler from a rule
+	pub fn new(rule: Rule) -> Autotiler {
+		let mut blob = vec![0; 256];
+		let mut variant = 0;
+		for (mask, entry) in blob.iter_mut().enumerate() {
+			if reduce(mask as u8) as usize == mask {
+				*entry = variant;
+				variant += 1;
+			}
+		}
+		Autotiler { rule, blob }
+	}
+
+	/// Compute the variant of every tile
+	pub fn apply<T, F>(&self, net: &TileNet<T>, terrain: F) -> TileNet<usize>
+		where F: FnMut(&T) -> u32
+	{
+		let (cols, rows) = net.get_size();
+		let mut variants = TileNet::new(cols.max(1), rows);
+		if let Some(span) = net.clamp_span((0, 0, cols - 1, rows.saturating_sub(1)), 0) {
+			self.fill(net, span, &mut variants, terrain);
+		}
+		variants
+	}
+
+	/// Recompute the variants after the tiles within a span have changed
+	///
+	/// Only the span and the tiles next to it are recomputed. If the sizes of the
+	/// nets differ, every variant is recomputed.
+	pub fn update<T, F>(&self,
+	                    net: &TileNet<T>,
+	                    span: Span,
+	                    variants: &mut TileNet<usize>,
+	                    terrain: F)
+		where F: FnMut(&T) -> u32
+	{
+		if net.get_size() != variants.get_size() {
+			*variants = self.apply(net, terrain);
+		} else if let Some(span) = net.clamp_span(span, 1) {
+			self.fill(net, span, variants, terrain);
+		}
+	}
+
+	/// Compute the variant of a single tile
+	pub fn variant<T, F>(&self,
+	                     net: &TileNet<T>,
+	                     p: (usize, usize),
+	                     mut terrain: F)
+	                     -> Option<usize>
+		where F: FnMut(&T) -> u32
+	{
+		let center = terrain(net.get(p)?);
+		let mut around = [center; 8];
+		for (value, offset) in around.iter_mut().zip(AROUND.iter()) {
+			if let Some(tile) = net.offset(p, (offset.0 as i32, offset.1 as i32)) {
+				*value = terrain(net.get(tile).unwrap());
+			}
+		}
+		let mask = around.iter()
+			.enumerate()
+			.filter(|&(_, x)| *x == center)
+			.fold(0u8, |mask, (bit, _)| mask | 1 << bit);
+		Some(match self.rule {
+			Rule::FourBit => {
+				(0..4).filter(|side| mask & 1 << (side * 2) != 0).fold(0, |x, side| x | 1 << side)
+			}
+			Rule::Blob => self.blob[reduce(mask) as usize],
+			Rule::Wang { ref tiles, fallback } => {
+				tiles.iter()
+					.find(|tile| {
+						tile.terrain == center &&
+						tile.edges.iter().enumerate().all(|(side, edge)| {
+							edge.map(|x| x == around[side * 2]).unwrap_or(true)
+						})
+					})
+					.map_or(fallback, |x| x.variant)
+			}
+		})
+	}
+
+	fn fill<T, F>(&self,
+	              net: &TileNet<T>,
+	              span: Span,
+	              variants: &mut TileNet<usize>,
+	              mut terrain: F)
+		where F: FnMut(&T) -> u32
+	{
+		for y in span.1..span.3 + 1 {
+			for x in span.0..span.2 + 1 {
+				let variant = self.variant(net, (x, y), &mut terrain).unwrap();
+				variants.set(&variant, (x, y));
+			}
+		}
+	}
+}
+
+/// Clear the corner bits whose two neighbouring edge bits are not both set
+fn reduce(mask: u8) -> u8 {
+	let mut reduced = mask;
+	for corner in &[1, 3, 5, 7] {
+		let (before, after) = (corner - 1, (corner + 1) % 8);
+		if mask & 1 << before == 0 || mask & 1 << after == 0 {
+			reduced &= !(1 << corner);
+		}
+	}
+	reduced
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn blob_has_47_variants() {
+		let tiler = Autotiler::new(Rule::Blob);
+		let mut seen: Vec<usize> =
+			(0..256).map(|mask| tiler.blob[reduce(mask as u8) as usize]).collect();
+		seen.sort_unstable();
+		seen.dedup();
+		assert_eq!(seen, (0..47).collect::<Vec<_>>());
+		let mut net: TileNet<usize> = TileNet::new(3, 3);
+		net.set(&1, (1, 1));
+		let variants = tiler.apply(&net, |x| *x as u32);
+		assert_eq!(variants.get((1, 1)), Some(&0));
+		assert_eq!(variants.get((0, 0)), Some(&44));
+	}
+
+	#[test]
+	fn wang_tiles() {
+		let tiles = vec![WangTile {
+			                 terrain: 1,
+			                 edges: [Some(0), None, None, None],
+			                 variant: 7,
+		                 },
+		                 WangTile {
+			                 terrain: 1,
+			                 edges: [None; 4],
+			                 variant: 3,
+		                 }];
+		let tiler = Autotiler::new(Rule::Wang {
+			tiles,
+			fallback: 9,
+		});
+		let mut net: TileNet<usize> = TileNet::new(3, 3);
+		net.set_box(&1, (0, 1), (2, 2));
+		let variants = tiler.apply(&net, |x| *x as u32);
+		// No tile has terrain 0, so the top row falls back
+		assert_eq!(variants.get_raw(), &[9, 9, 9, 7, 7, 7, 3, 3, 3]);
+		assert_eq!(tiler.variant(&net, (1, 0), |x| *x as u32), Some(9));
+	}
+
+	#[test]
+	fn update_matches_apply() {
+		let mut seed = 11u32;
+		let mut random = move || {
+			seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+			(seed >> 16) as usize
+		};
+		let mut net: TileNet<usize> = TileNet::from_iter(16, (0..16 * 16).map(|_| random() % 3));
+		for rule in [Rule::FourBit, Rule::Blob] {
+			let tiler = Autotiler::new(rule);
+			let mut variants = tiler.apply(&net, |x| *x as u32);
+			for _ in 0..30 {
+				let (x, y) = (random() % 14, random() % 14);
+				let span = {
+					let mut proxy = net.prepare();
+					proxy.set_box(&(random() % 3), (x, y), (x + random() % 3, y + random() % 3));
+					proxy.get_span()
+				};
+				tiler.update(&net, span, &mut variants, |x| *x as u32);
+				let fresh = tiler.apply(&net, |x| *x as u32);
+				assert_eq!(variants.get_raw(), fresh.get_raw());
+			}
+		}
+	}
+}
@@ -1,7 +1,14 @@
+use std::cell::RefCell;
 use std::fmt;
 use std::cmp::min;
-pub use self::tilenet::{TileNet, TileNetProxy};
+pub use self::autotile::{Autotiler, Rule, WangTile};
+pub use self::neighbours::{Border, Neighbours, NeighboursMut};
+pub use self::pattern::{Matches, Pattern, PatternCell, Transform};
+pub use self::tilenet::{Span, TileNet, TileNetProxy};
 
+mod autotile;
+mod neighbours;
+mod pattern;
 mod tilenet;
 
 /// Tile iterator returning tiles from the `tile_net::TileNet`.
@@ -51,6 +58,8 @@ pub struct TileSet<'a, T, I>
 	tilenet: &'a TileNet<T>,
 	points: I,
 	last_coord: (i32, i32),
+	last_tile: Option<(i32, i32)>,
+	blocking: Option<&'a RefCell<Vec<(i32, i32)>>>,
 }
 
 impl<'a, T, I> TileSet<'a, T, I>
@@ -64,6 +73,35 @@ impl<'a, T, I> TileSet<'a, T, I>
 	pub fn get_coords(&self) -> (i32, i32) {
 		self.last_coord
 	}
+
+	/// Get the net the tiles are read from
+	///
+	/// Lets `Collable::resolve` look past the tiles in the set, for example to move
+	/// with `TileNet::slide_box` instead of shrinking the movement and retrying.
+	pub fn get_net(&self) -> &'a TileNet<T> {
+		self.tilenet
+	}
+
+	/// Mark the tile that was returned last as blocking the movement
+	///
+	/// Call this from `Collable::resolve` right after reading the tile that makes
+	/// you reject a movement; the marked tiles are listed in `SolveReport::blocking`.
+	/// Does nothing before the first tile is returned, or for sets that were not
+	/// created by `Collable::solve`.
+	pub fn block(&self) {
+		if let (Some(tile), Some(blocking)) = (self.last_tile, self.blocking) {
+			let mut blocking = blocking.borrow_mut();
+			if !blocking.contains(&tile) {
+				blocking.push(tile);
+			}
+		}
+	}
+
+	/// Record the tiles passed to `block` in a list
+	pub(crate) fn recording(mut self, blocking: &'a RefCell<Vec<(i32, i32)>>) -> Self {
+		self.blocking = Some(blocking);
+		self
+	}
 }
 
 impl<'a, T, I> Iterator for TileSet<'a, T, I>
@@ -77,6 +115,7 @@ impl<'a, T, I> Iterator for TileSet<'a, T, I>
 				self.last_coord = point;
 				if point.0 >= 0 && point.1 >= 0 && point.0 < self.tilenet.get_size().0 as i32 &&
 				   point.1 < self.tilenet.get_size().1 as i32 {
+					self.last_tile = Some(point);
 					return self.tilenet.get((point.0 as usize, point.1 as usize));
 				} else {
 					continue;
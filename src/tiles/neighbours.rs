@@ -0,0 +1,301 @@
+use std::slice;
+use std::vec;
+use super::TileNet;
+use super::super::Connectivity;
+
+/// How coordinates that fall outside of the `TileNet` are handled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Border {
+	/// Move the coordinate onto the nearest edge tile, which may repeat edge tiles
+	Clamp,
+	/// Leave the coordinate out
+	Skip,
+	/// Wrap the coordinate around to the other side of the net
+	Wrap,
+}
+
+/// Iterator over tiles around a center tile
+///
+/// Created by `TileNet::neighbours`, `TileNet::ring`, and `TileNet::circle`.
+/// With `Border::Clamp` or `Border::Wrap` the same tile may be returned more than once.
+#[derive(Clone)]
+pub struct Neighbours<'a, T>
+	where T: 'a
+{
+	tilenet: &'a TileNet<T>,
+	points: vec::IntoIter<(i64, i64)>,
+	border: Border,
+}
+
+impl<'a, T> Iterator for Neighbours<'a, T>
+	where T: 'a
+{
+	type Item = ((usize, usize), &'a T);
+	fn next(&mut self) -> Option<Self::Item> {
+		for point in self.points.by_ref() {
+			if let Some(p) = resolve(self.tilenet.get_size(), point, self.border) {
+				return self.tilenet.get(p).map(|x| (p, x));
+			}
+		}
+		None
+	}
+}
+
+/// Mutable iterator over tiles around a center tile
+///
+/// Unlike `Neighbours`, every tile is returned at most once. The tiles are
+/// returned in row-major order.
+pub struct NeighboursMut<'a, T>
+	where T: 'a
+{
+	cols: usize,
+	tiles: slice::IterMut<'a, T>,
+	indices: vec::IntoIter<usize>,
+	position: usize,
+}
+
+impl<'a, T> Iterator for NeighboursMut<'a, T>
+	where T: 'a
+{
+	type Item = ((usize, usize), &'a mut T);
+	fn next(&mut self) -> Option<Self::Item> {
+		let index = self.indices.next()?;
+		let skip = index - self.position;
+		self.position = index + 1;
+		let cols = self.cols;
+		self.tiles.nth(skip).map(|x| ((index % cols, index / cols), x))
+	}
+}
+
+impl<T> TileNet<T> {
+	/// Iterate over the direct neighbours of a tile
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let net: TileNet<usize> = TileNet::new(10, 10);
+	/// assert_eq!(net.neighbours((0, 0), Connectivity::Eight, Border::Skip).count(), 3);
+	/// assert_eq!(net.neighbours((0, 0), Connectivity::Eight, Border::Wrap).count(), 8);
+	/// let clamped: Vec<_> = net.neighbours((0, 0), Connectivity::Four, Border::Clamp)
+	///   .map(|x| x.0)
+	///   .collect();
+	/// assert_eq!(clamped, vec![(1, 0), (0, 1), (0, 0), (0, 0)]);
+	/// ```
+	pub fn neighbours(&self,
+	                  p: (usize, usize),
+	                  connectivity: Connectivity,
+	                  border: Border)
+	                  -> Neighbours<'_, T> {
+		self.around(neighbour_points(p, connectivity), border)
+	}
+
+	/// Iterate over the square ring of tiles at Chebyshev distance `radius` from a tile
+	///
+	/// A radius of 0 gives only the tile itself.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let net: TileNet<usize> = TileNet::new(10, 10);
+	/// assert_eq!(net.ring((5, 5), 2, Border::Skip).count(), 16);
+	/// assert!(net.ring((5, 5), 2, Border::Skip).all(|((x, y), _)| {
+	///   (x as i32 - 5).abs().max((y as i32 - 5).abs()) == 2
+	/// }));
+	/// ```
+	pub fn ring(&self, p: (usize, usize), radius: usize, border: Border) -> Neighbours<'_, T> {
+		self.around(ring_points(p, radius), border)
+	}
+
+	/// Iterate over the tiles of a Bresenham circle around a tile
+	///
+	/// The tiles are returned in order of their angle around the center.
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let net: TileNet<usize> = TileNet::new(20, 20);
+	/// let circle: Vec<_> = net.circle((10, 10), 3, Border::Skip).map(|x| x.0).collect();
+	/// assert!(circle.contains(&(13, 10)));
+	/// assert!(circle.contains(&(10, 7)));
+	/// assert!(!circle.contains(&(13, 13)));
+	/// ```
+	pub fn circle(&self, p: (usize, usize), radius: usize, border: Border) -> Neighbours<'_, T> {
+		self.around(circle_points(p, radius), border)
+	}
+
+	/// Mutably iterate over the direct neighbours of a tile
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let mut net: TileNet<usize> = TileNet::new(3, 3);
+	/// for (_, tile) in net.neighbours_mut((1, 1), Connectivity::Four, Border::Skip) {
+	///   *tile = 1;
+	/// }
+	/// assert_eq!(net.get_raw(), &[0, 1, 0, 1, 0, 1, 0, 1, 0]);
+	/// ```
+	pub fn neighbours_mut(&mut self,
+	                      p: (usize, usize),
+	                      connectivity: Connectivity,
+	                      border: Border)
+	                      -> NeighboursMut<'_, T> {
+		self.around_mut(neighbour_points(p, connectivity), border)
+	}
+
+	/// Mutably iterate over the square ring of tiles at Chebyshev distance `radius`
+	pub fn ring_mut(&mut self,
+	                p: (usize, usize),
+	                radius: usize,
+	                border: Border)
+	                -> NeighboursMut<'_, T> {
+		self.around_mut(ring_points(p, radius), border)
+	}
+
+	/// Mutably iterate over the tiles of a Bresenham circle around a tile
+	pub fn circle_mut(&mut self,
+	                  p: (usize, usize),
+	                  radius: usize,
+	                  border: Border)
+	                  -> NeighboursMut<'_, T> {
+		self.around_mut(circle_points(p, radius), border)
+	}
+
+	fn around(&self, points: Vec<(i64, i64)>, border: Border) -> Neighbours<'_, T> {
+		Neighbours {
+			tilenet: self,
+			points: points.into_iter(),
+			border,
+		}
+	}
+
+	fn around_mut(&mut self, points: Vec<(i64, i64)>, border: Border) -> NeighboursMut<'_, T> {
+		let size = self.get_size();
+		let mut indices: Vec<usize> = points.into_iter()
+			.filter_map(|point| resolve(size, point, border))
+			.map(|p| p.0 + p.1 * size.0)
+			.collect();
+		indices.sort_unstable();
+		indices.dedup();
+		NeighboursMut {
+			cols: size.0,
+			tiles: self.get_raw_mut().iter_mut(),
+			indices: indices.into_iter(),
+			position: 0,
+		}
+	}
+}
+
+/// Map a coordinate onto the net according to the border rule
+fn resolve(size: (usize, usize), point: (i64, i64), border: Border) -> Option<(usize, usize)> {
+	let (cols, rows) = (size.0 as i64, size.1 as i64);
+	if cols == 0 || rows == 0 {
+		return None;
+	}
+	match border {
+		Border::Skip => {
+			if point.0 < 0 || point.1 < 0 || point.0 >= cols || point.1 >= rows {
+				None
+			} else {
+				Some((point.0 as usize, point.1 as usize))
+			}
+		}
+		Border::Clamp => {
+			Some((point.0.max(0).min(cols - 1) as usize,
+			      point.1.max(0).min(rows - 1) as usize))
+		}
+		Border::Wrap => {
+			Some((point.0.rem_euclid(cols) as usize, point.1.rem_euclid(rows) as usize))
+		}
+	}
+}
+
+fn neighbour_points(p: (usize, usize), connectivity: Connectivity) -> Vec<(i64, i64)> {
+	connectivity.offsets()
+		.iter()
+		.map(|offset| (p.0 as i64 + offset.0 as i64, p.1 as i64 + offset.1 as i64))
+		.collect()
+}
+
+fn ring_points(p: (usize, usize), radius: usize) -> Vec<(i64, i64)> {
+	let (x, y, r) = (p.0 as i64, p.1 as i64, radius as i64);
+	if r == 0 {
+		return vec![(x, y)];
+	}
+	let mut points = Vec::with_capacity(8 * radius);
+	for offset in -r..r + 1 {
+		points.push((x + offset, y - r));
+	}
+	for offset in -r + 1..r + 1 {
+		points.push((x + r, y + offset));
+	}
+	for offset in (-r..r).rev() {
+		points.push((x + offset, y + r));
+	}
+	for offset in (-r + 1..r).rev() {
+		points.push((x - r, y + offset));
+	}
+	points
+}
+
+/// Midpoint circle algorithm, sorted by angle
+fn circle_points(p: (usize, usize), radius: usize) -> Vec<(i64, i64)> {
+	let r = radius as i64;
+	let mut octant = vec![];
+	let (mut x, mut y, mut error) = (r, 0, 1 - r);
+	while x >= y {
+		octant.push((x, y));
+		y += 1;
+		if error < 0 {
+			error += 2 * y + 1;
+		} else {
+			x -= 1;
+			error += 2 * (y - x) + 1;
+		}
+	}
+	let mut offsets: Vec<(i64, i64)> = octant.iter()
+		.flat_map(|&(x, y)| {
+			vec![(x, y), (y, x), (-y, x), (-x, y), (-x, -y), (-y, -x), (y, -x), (x, -y)]
+		})
+		.collect();
+	offsets.sort_unstable();
+	offsets.dedup();
+	offsets.sort_by(|a, b| {
+		let angle = |x: &(i64, i64)| (x.1 as f64).atan2(x.0 as f64);
+		angle(a).partial_cmp(&angle(b)).unwrap()
+	});
+	offsets.into_iter().map(|x| (p.0 as i64 + x.0, p.1 as i64 + x.1)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn borders() {
+		let net: TileNet<usize> = TileNet::from_iter(3, 0..9);
+		let wrapped: Vec<usize> = net.ring((0, 0), 1, Border::Wrap).map(|x| *x.1).collect();
+		assert_eq!(wrapped, vec![8, 6, 7, 1, 4, 3, 5, 2]);
+		assert_eq!(net.ring((0, 0), 5, Border::Skip).count(), 0);
+		assert_eq!(net.ring((0, 0), 5, Border::Clamp).count(), 40);
+	}
+
+	#[test]
+	fn mutable_once() {
+		let mut net: TileNet<usize> = TileNet::new(2, 2);
+		for (_, tile) in net.ring_mut((0, 0), 1, Border::Wrap) {
+			*tile += 1;
+		}
+		assert_eq!(net.get_raw(), &[0, 1, 1, 1]);
+		for (_, tile) in net.circle_mut((0, 0), 4, Border::Clamp) {
+			*tile += 1;
+		}
+		assert_eq!(net.get_raw(), &[1, 2, 2, 2]);
+	}
+
+	#[test]
+	fn circle_radius() {
+		let net: TileNet<usize> = TileNet::new(40, 40);
+		for radius in 0..15 {
+			for ((x, y), _) in net.circle((20, 20), radius, Border::Skip) {
+				let distance = ((x as f32 - 20.0).powi(2) + (y as f32 - 20.0).powi(2)).sqrt();
+				assert!((distance - radius as f32).abs() < 0.75);
+			}
+		}
+	}
+}
@@ -0,0 +1,284 @@
+use std::rc::Rc;
+use super::{TileNet, TileView};
+
+/// A single cell of a `Pattern`
+pub enum PatternCell<T> {
+	/// Matches any tile
+	Any,
+	/// Matches tiles for which the predicate holds
+	Is(Rc<dyn Fn(&T) -> bool>),
+}
+
+impl<T> PatternCell<T> {
+	/// Create a cell from a predicate
+	pub fn is<F>(predicate: F) -> PatternCell<T>
+		where F: Fn(&T) -> bool + 'static
+	{
+		PatternCell::Is(Rc::new(predicate))
+	}
+}
+
+impl<T> Clone for PatternCell<T> {
+	fn clone(&self) -> PatternCell<T> {
+		match *self {
+			PatternCell::Any => PatternCell::Any,
+			PatternCell::Is(ref predicate) => PatternCell::Is(predicate.clone()),
+		}
+	}
+}
+
+/// Orientation in which a `Pattern` was found
+///
+/// The pattern is first mirrored horizontally, if at all, and then rotated clockwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transform {
+	/// Number of clockwise quarter turns, from 0 to 3
+	pub quarter_turns: u8,
+	/// Whether the pattern was mirrored horizontally
+	pub mirrored: bool,
+}
+
+/// One orientation of a pattern, as the offsets of the cells that are not `PatternCell::Any`
+struct Variant {
+	transform: Transform,
+	width: usize,
+	height: usize,
+	cells: Vec<(usize, usize, usize)>,
+}
+
+/// Small grid of cells to search for in a `TileNet`
+///
+/// Orientations that look the same are only searched once. Two cells look the
+/// same when one is a clone of the other.
+///
+/// ```
+/// use tile_net::*;
+/// let mut net: TileNet<usize> = TileNet::new(6, 4);
+/// net.set_row(&1, 3);
+/// net.set(&1, (2, 2));
+/// // A floor tile with air above it
+/// let air = PatternCell::is(|x: &usize| *x == 0);
+/// let wall = PatternCell::is(|x: &usize| *x == 1);
+/// let floor = Pattern::new(1, vec![air, wall]);
+/// let anchors: Vec<_> = floor.matches(&net).map(|x| x.0).collect();
+/// assert_eq!(anchors, vec![(2, 1), (0, 2), (1, 2), (3, 2), (4, 2), (5, 2)]);
+/// ```
+pub struct Pattern<T> {
+	width: usize,
+	height: usize,
+	cells: Vec<PatternCell<T>>,
+	variants: Vec<Variant>,
+}
+
+impl<T> Pattern<T> {
+	/// Compile a pattern from its cells in row-major order
+	///
+	/// The height of the pattern is the number of cells divided by the width.
+	pub fn new(width: usize, cells: Vec<PatternCell<T>>) -> Pattern<T> {
+		assert!(width > 0 && cells.len() / width * width == cells.len(),
+		        "the cells must fill whole rows of the pattern");
+		let mut pattern = Pattern {
+			width,
+			height: cells.len() / width,
+			cells,
+			variants: vec![],
+		};
+		pattern.add(Transform {
+			quarter_turns: 0,
+			mirrored: false,
+		});
+		pattern
+	}
+
+	/// Also search for the pattern rotated by 90, 180, and 270 degrees
+	pub fn rotations(mut self) -> Pattern<T> {
+		let existing: Vec<Transform> = self.variants.iter().map(|x| x.transform).collect();
+		for transform in existing {
+			for turns in 1..4 {
+				self.add(Transform {
+					quarter_turns: turns,
+					mirrored: transform.mirrored,
+				});
+			}
+		}
+		self
+	}
+
+	/// Also search for the mirror images of the pattern
+	pub fn mirrors(mut self) -> Pattern<T> {
+		let existing: Vec<Transform> = self.variants.iter().map(|x| x.transform).collect();
+		for transform in existing {
+			self.add(Transform {
+				quarter_turns: transform.quarter_turns,
+				mirrored: true,
+			});
+		}
+		self
+	}
+
+	/// Find every match in a net
+	///
+	/// Returns the top-left anchor of each match together with the orientation it
+	/// was found in. A tile may anchor several orientations.
+	pub fn matches<'a>(&'a self, net: &'a TileNet<T>) -> Matches<'a, T> {
+		let (cols, rows) = net.get_size();
+		Matches {
+			pattern: self,
+			tilenet: net,
+			rectangle: (0, cols, 0, rows),
+			anchor: (0, 0),
+			variant: 0,
+		}
+	}
+
+	/// Find every match that lies entirely within the rectangle of a view
+	pub fn matches_in<'a>(&'a self, view: &TileView<'a, T>) -> Matches<'a, T> {
+		Matches {
+			pattern: self,
+			tilenet: view.tilenet,
+			rectangle: view.rectangle,
+			anchor: (view.rectangle.0, view.rectangle.2),
+			variant: 0,
+		}
+	}
+
+	/// Check if two cells are clones of each other
+	fn same_cell(&self, a: usize, b: usize) -> bool {
+		match (&self.cells[a], &self.cells[b]) {
+			(PatternCell::Is(a), PatternCell::Is(b)) => Rc::ptr_eq(a, b),
+			_ => false,
+		}
+	}
+
+	/// Add an orientation unless it coincides with an existing one
+	fn add(&mut self, transform: Transform) {
+		let (mut width, mut height) = (self.width, self.height);
+		let mut cells: Vec<(usize, usize, usize)> = (0..self.cells.len())
+			.filter(|index| match self.cells[*index] {
+				PatternCell::Any => false,
+				PatternCell::Is(_) => true,
+			})
+			.map(|index| (index % width, index / width, index))
+			.collect();
+		if transform.mirrored {
+			for cell in &mut cells {
+				cell.0 = width - 1 - cell.0;
+			}
+		}
+		for _ in 0..transform.quarter_turns {
+			for cell in &mut cells {
+				*cell = (height - 1 - cell.1, cell.0, cell.2);
+			}
+			::std::mem::swap(&mut width, &mut height);
+		}
+		cells.sort_unstable();
+		let variant = Variant {
+			transform,
+			width,
+			height,
+			cells,
+		};
+		let duplicate = self.variants.iter().any(|x| {
+			x.width == variant.width && x.height == variant.height &&
+			x.cells.len() == variant.cells.len() &&
+			x.cells.iter().zip(variant.cells.iter()).all(|(a, b)| {
+				a.0 == b.0 && a.1 == b.1 && self.same_cell(a.2, b.2)
+			})
+		});
+		if !duplicate {
+			self.variants.push(variant);
+		}
+	}
+}
+
+/// Iterator over the matches of a `Pattern`
+///
+/// Created by `Pattern::matches` and `Pattern::matches_in`.
+pub struct Matches<'a, T>
+	where T: 'a
+{
+	pattern: &'a Pattern<T>,
+	tilenet: &'a TileNet<T>,
+	rectangle: (usize, usize, usize, usize),
+	anchor: (usize, usize),
+	variant: usize,
+}
+
+impl<'a, T> Matches<'a, T>
+	where T: 'a
+{
+	fn fits(&self, variant: &Variant) -> bool {
+		let (x, y) = self.anchor;
+		let (cols, rows) = self.tilenet.get_size();
+		x + variant.width <= self.rectangle.1.min(cols) &&
+		y + variant.height <= self.rectangle.3.min(rows) &&
+		variant.cells.iter().all(|&(dx, dy, index)| {
+			match (&self.pattern.cells[index], self.tilenet.get((x + dx, y + dy))) {
+				(PatternCell::Is(predicate), Some(tile)) => predicate(tile),
+				_ => false,
+			}
+		})
+	}
+}
+
+impl<'a, T> Iterator for Matches<'a, T>
+	where T: 'a
+{
+	type Item = ((usize, usize), Transform);
+	fn next(&mut self) -> Option<Self::Item> {
+		let bottom = self.rectangle.3.min(self.tilenet.get_size().1);
+		let right = self.rectangle.1.min(self.tilenet.get_size().0);
+		while self.anchor.1 < bottom && self.anchor.0 < right {
+			while let Some(variant) = self.pattern.variants.get(self.variant) {
+				self.variant += 1;
+				if self.fits(variant) {
+					return Some((self.anchor, variant.transform));
+				}
+			}
+			self.variant = 0;
+			self.anchor.0 += 1;
+			if self.anchor.0 >= right {
+				self.anchor.0 = self.rectangle.0;
+				self.anchor.1 += 1;
+			}
+		}
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn corner() -> Pattern<usize> {
+		let wall = PatternCell::is(|x: &usize| *x == 1);
+		let air = PatternCell::is(|x: &usize| *x == 0);
+		Pattern::new(2, vec![wall.clone(), wall.clone(), wall, air])
+	}
+
+	#[test]
+	fn rotations_and_mirrors() {
+		let mut net: TileNet<usize> = TileNet::new(5, 5);
+		net.set_row(&1, 0);
+		net.set_row(&1, 4);
+		net.set_col(&1, 0);
+		net.set_col(&1, 4);
+		let found: Vec<_> = corner().matches(&net).collect();
+		assert_eq!(found.len(), 1);
+		assert_eq!(found[0].0, (0, 0));
+		let found: Vec<_> = corner().rotations().matches(&net).map(|x| x.0).collect();
+		assert_eq!(found, vec![(0, 0), (3, 0), (0, 3), (3, 3)]);
+		// The corner is its own mirror image along the diagonal
+		assert_eq!(corner().rotations().mirrors().variants.len(), 4);
+	}
+
+	#[test]
+	fn within_view() {
+		let mut net: TileNet<usize> = TileNet::new(5, 5);
+		net.set_row(&1, 0);
+		net.set_col(&1, 0);
+		assert_eq!(corner().matches_in(&net.view_box((0, 2, 0, 2))).count(), 1);
+		assert_eq!(corner().matches_in(&net.view_box((1, 5, 0, 5))).count(), 0);
+		assert_eq!(corner().matches_in(&net.view_box((0, 1, 0, 5))).count(), 0);
+	}
+}
@@ -1,5 +1,6 @@
 extern crate interleave;
 
+use std::cmp::min;
 use std::fmt;
 use super::{TileView, TileSet};
 
@@ -71,6 +72,11 @@ impl<'a, T> TileNetProxy<'a, T>
 		(self.min_x, self.min_y, self.max_x, self.max_y)
 	}
 
+	/// Get the `TileNet` being edited
+	pub fn get_tilenet(&self) -> &TileNet<T> {
+		self.tilenet
+	}
+
 	/// Set a box
 	///
 	/// Start should be less than stop
@@ -166,11 +172,6 @@ impl<T> TileNet<T>
 		}
 	}
 
-	/// Get the raw array behind the tilenet
-	pub fn get_raw(&self) -> &[T] {
-		self.map.as_slice()
-	}
-
 
 	/// Use isizes to denote indices to prevent underflow
 	pub fn set_box_isize(&mut self, value: &T, start: (isize, isize), stop: (isize, isize)) {
@@ -278,6 +279,16 @@ impl<T> TileNet<T>
 }
 
 impl<T> TileNet<T> {
+	/// Get the raw array behind the tilenet
+	pub fn get_raw(&self) -> &[T] {
+		self.map.as_slice()
+	}
+
+	/// Get the mutable raw array behind the tilenet
+	pub fn get_raw_mut(&mut self) -> &mut [T] {
+		self.map.as_mut_slice()
+	}
+
 	/// Compute the row count
 	pub fn row_count(&self) -> usize {
 		self.map.len() / self.cols
@@ -302,6 +313,39 @@ impl<T> TileNet<T> {
 		(self.cols, self.row_count())
 	}
 
+	/// Offset a coordinate
+	///
+	/// Returns `None` if the resulting coordinate lies outside of the net.
+	pub fn offset(&self, p: (usize, usize), by: (i32, i32)) -> Option<(usize, usize)> {
+		let x = p.0 as i64 + by.0 as i64;
+		let y = p.1 as i64 + by.1 as i64;
+		let (cols, rows) = self.get_size();
+		if x < 0 || y < 0 || x >= cols as i64 || y >= rows as i64 {
+			None
+		} else {
+			Some((x as usize, y as usize))
+		}
+	}
+
+	/// Grow a span by a margin on every side and clamp it to the net
+	///
+	/// Returns `None` if the span is empty, which is the case for a `TileNetProxy`
+	/// that has not changed any tiles yet.
+	pub fn clamp_span(&self, span: Span, margin: usize) -> Option<Span> {
+		let (cols, rows) = self.get_size();
+		if span.0 > span.2 || span.1 > span.3 || cols == 0 || rows == 0 {
+			return None;
+		}
+		let (left, top) = (span.0.saturating_sub(margin), span.1.saturating_sub(margin));
+		if left >= cols || top >= rows {
+			return None;
+		}
+		Some((left,
+		      top,
+		      min(span.2.saturating_add(margin), cols - 1),
+		      min(span.3.saturating_add(margin), rows - 1)))
+	}
+
 	/// Create a proxy view that iterates over all tiles
 	pub fn view_all(&self) -> TileView<T> {
 		TileView::new(self, (0, self.cols, 0, self.map.len() / self.cols))
@@ -344,6 +388,8 @@ impl<T> TileNet<T> {
 			tilenet: self,
 			points: list,
 			last_coord: (0, 0),
+			last_tile: None,
+			blocking: None,
 		}
 	}
 }
@@ -0,0 +1,221 @@
+use std::borrow::Cow;
+use interleave::{IterList, MultiIter};
+use super::{Line3, Vector3, VoxelNet, VoxelSet};
+use super::super::collable::resolve_loop;
+use super::super::SolveReport;
+
+/// A vertex iterator for `VoxelCollable`, the 3D counterpart of `Points`
+///
+/// Every iteration returns the next point + offset. Keep the points at most one
+/// voxel apart, so no voxel can pass between them.
+pub struct VoxelPoints<'a> {
+	index: usize,
+	offset: Vector3,
+	points: Cow<'a, [(f32, f32, f32)]>,
+}
+
+impl<'a> VoxelPoints<'a> {
+	/// Create a new points iterator
+	///
+	/// ```
+	/// use tile_net::*;
+	/// let initial = vec![(0.0, 0.0, 0.0), (1.0, 0.0, 2.0)];
+	/// let mut points = VoxelPoints::new(Vector3(3.0, 4.0, 5.0), &initial);
+	/// assert_eq!(points.next(), Some((3.0, 4.0, 5.0)));
+	/// assert_eq!(points.next(), Some((4.0, 4.0, 7.0)));
+	/// ```
+	pub fn new(offset: Vector3, points: &'a [(f32, f32, f32)]) -> VoxelPoints<'a> {
+		VoxelPoints {
+			index: 0,
+			offset,
+			points: Cow::Borrowed(points),
+		}
+	}
+
+	/// Create a points iterator that owns its points
+	pub fn owned(offset: Vector3, points: Vec<(f32, f32, f32)>) -> VoxelPoints<'static> {
+		VoxelPoints {
+			index: 0,
+			offset,
+			points: Cow::Owned(points),
+		}
+	}
+}
+
+impl<'a> Iterator for VoxelPoints<'a> {
+	type Item = (f32, f32, f32);
+	fn next(&mut self) -> Option<Self::Item> {
+		let point = self.points.get(self.index)?;
+		self.index += 1;
+		let ret = Vector3::from_tuple(*point) + self.offset;
+		Some((ret.0, ret.1, ret.2))
+	}
+}
+
+/// Outcome of a `VoxelCollable::solve` call
+///
+/// `blocking` lists the voxels that `resolve` marked with `VoxelSet::block`.
+pub type VoxelReport = SolveReport<Vector3, (i32, i32, i32)>;
+
+/// State that is passed through a single `VoxelCollable::solve` call
+pub trait VoxelState {
+	/// Returns the movement vector that the next iteration should try
+	fn queued(&self) -> Vector3;
+}
+
+/// Trait for dynamic objects so they can check collisions with a `VoxelNet`
+///
+/// Works like `Collable`: the lines from every point to the point moved by the
+/// queued movement are traced through the voxels, and `resolve` is asked to
+/// accept the movement or to queue another one.
+///
+/// ```
+/// use tile_net::*;
+/// struct Cube(Vector3, Vec<(f32, f32, f32)>);
+/// struct Fall(Vector3);
+/// impl VoxelState for Fall {
+///   fn queued(&self) -> Vector3 {
+///     self.0
+///   }
+/// }
+/// impl VoxelCollable<usize, Fall> for Cube {
+///   fn points(&self) -> VoxelPoints<'_> {
+///     VoxelPoints::new(self.0, &self.1)
+///   }
+///   fn resolve<I>(&mut self, mut set: VoxelSet<usize, I>, state: &mut Fall) -> bool
+///     where I: Iterator<Item = (i32, i32, i32)>
+///   {
+///     if set.all(|x| *x == 0) {
+///       self.0 += state.0;
+///       true
+///     } else {
+///       state.0.scale(0.5);
+///       false
+///     }
+///   }
+/// }
+/// let mut net: VoxelNet<usize> = VoxelNet::new(5, 5, 5);
+/// net.set_box(&1, (0, 4, 0), (4, 4, 4));
+/// let mut cube = Cube(Vector3(2.25, 0.25, 2.25), vec![(0.0, 0.0, 0.0), (0.5, 0.5, 0.5)]);
+/// let report = cube.solve(&net, &mut Fall(Vector3(0.0, 4.0, 0.0)));
+/// assert!(!report.exhausted);
+/// assert_eq!(cube.0, Vector3(2.25, 2.25, 2.25));
+/// ```
+pub trait VoxelCollable<T, S>
+	where S: VoxelState
+{
+	/// Returns the set of points associated with this object
+	fn points(&self) -> VoxelPoints<'_>;
+
+	/// Resolve the movement: you get a set of voxels and you decide what to do with them
+	///
+	/// Return true to accept the movement, after adjusting the position of your
+	/// object. Return false after queueing another movement in the state to try again.
+	/// Call `VoxelSet::block` after reading a voxel that stops you, so `solve` can report it.
+	fn resolve<I>(&mut self, set: VoxelSet<T, I>, state: &mut S) -> bool
+		where I: Iterator<Item = (i32, i32, i32)>;
+
+	/// Called at the beginning of `solve`
+	fn presolve(&mut self, _state: &mut S) {}
+
+	/// Called at the end of `solve`
+	fn postsolve(&mut self, _collided_once: bool, _resolved: bool, _state: &mut S) {}
+
+	/// The number of iterations `solve` may use
+	fn max_iterations(&self) -> usize {
+		30
+	}
+
+	/// Run the resolve loop, see `Collable::solve`
+	fn solve(&mut self, net: &VoxelNet<T>, state: &mut S) -> VoxelReport {
+		let max_iterations = self.max_iterations();
+		self.solve_with(net, state, max_iterations)
+	}
+
+	/// Same as `solve`, but with the iteration limit given for this call only
+	fn solve_with(&mut self,
+	              net: &VoxelNet<T>,
+	              state: &mut S,
+	              max_iterations: usize)
+	              -> VoxelReport {
+		self.presolve(state);
+		let (iterations, resolved, blocking) = resolve_loop(max_iterations, |blocking| {
+			let points = self.voxels(state.queued());
+			self.resolve(net.collide_set(points).recording(blocking), state)
+		});
+		let collided_once = iterations > resolved as usize;
+		self.postsolve(collided_once, resolved, state);
+		VoxelReport {
+			iterations,
+			exhausted: !resolved,
+			queued: state.queued(),
+			blocking,
+		}
+	}
+
+	/// Gives us the voxels along the lines of all points, interleaved so the
+	/// voxels closest to the start come first
+	fn voxels(&self, queued: Vector3) -> MultiIter<(i32, i32, i32)> {
+		let mut multi = interleave!((i32, i32, i32););
+		for point in self.points() {
+			let from = Vector3::from_tuple(point);
+			multi.push(Box::new(Line3(from, from + queued).supercover()));
+		}
+		multi
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct Mover(Vector3);
+
+	impl VoxelState for Mover {
+		fn queued(&self) -> Vector3 {
+			self.0
+		}
+	}
+
+	struct Dot(Vector3);
+
+	impl VoxelCollable<usize, Mover> for Dot {
+		fn points(&self) -> VoxelPoints<'_> {
+			VoxelPoints::owned(self.0, vec![(0.0, 0.0, 0.0)])
+		}
+
+		fn resolve<I>(&mut self, mut set: VoxelSet<usize, I>, state: &mut Mover) -> bool
+			where I: Iterator<Item = (i32, i32, i32)>
+		{
+			let mut blocked = false;
+			while let Some(voxel) = set.next() {
+				if *voxel == 1 {
+					set.block();
+					blocked = true;
+				}
+			}
+			if blocked {
+				state.0.scale(0.5);
+			} else {
+				self.0 += state.0;
+			}
+			!blocked
+		}
+	}
+
+	#[test]
+	fn report_blocking_voxel() {
+		let mut net: VoxelNet<usize> = VoxelNet::new(8, 8, 8);
+		net.set(&1, (6, 3, 3));
+		let mut dot = Dot(Vector3(1.5, 3.5, 3.5));
+		let report = dot.solve(&net, &mut Mover(Vector3(6.0, 0.0, 0.0)));
+		assert_eq!(report.iterations, 2);
+		assert_eq!(report.blocking, vec![(6, 3, 3)]);
+		assert_eq!(dot.0, Vector3(4.5, 3.5, 3.5));
+		// Every voxel is read, including the ones past the blocking voxel
+		net.set(&1, (7, 3, 3));
+		let report = dot.solve_with(&net, &mut Mover(Vector3(8.0, 0.0, 0.0)), 1);
+		assert!(report.exhausted);
+		assert_eq!(report.blocking, vec![(6, 3, 3), (7, 3, 3)]);
+	}
+}
@@ -0,0 +1,189 @@
+use std::ops::{Add, AddAssign, Sub};
+
+/// Describe a point in 3-space
+///
+/// ```
+/// use tile_net::Vector3;
+/// let point = Vector3(0.5, 1.0, 2.0);
+/// assert_eq!(point + Vector3(1.0, 1.0, 1.0), Vector3(1.5, 2.0, 3.0));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vector3(pub f32, pub f32, pub f32);
+
+impl Vector3 {
+	/// Create a vector from a float tuple
+	pub fn from_tuple(tuple: (f32, f32, f32)) -> Vector3 {
+		Vector3(tuple.0, tuple.1, tuple.2)
+	}
+
+	/// Compute the 2-norm squared
+	pub fn norm2sq(&self) -> f32 {
+		self.0 * self.0 + self.1 * self.1 + self.2 * self.2
+	}
+
+	/// Scale all vector elements linearly
+	pub fn scale(&mut self, factor: f32) {
+		self.0 *= factor;
+		self.1 *= factor;
+		self.2 *= factor;
+	}
+}
+
+impl Add for Vector3 {
+	type Output = Vector3;
+
+	fn add(self, other: Vector3) -> Vector3 {
+		Vector3(self.0 + other.0, self.1 + other.1, self.2 + other.2)
+	}
+}
+
+impl AddAssign for Vector3 {
+	fn add_assign(&mut self, rhs: Vector3) {
+		self.0 += rhs.0;
+		self.1 += rhs.1;
+		self.2 += rhs.2;
+	}
+}
+
+impl Sub for Vector3 {
+	type Output = Vector3;
+
+	fn sub(self, other: Vector3) -> Vector3 {
+		Vector3(self.0 - other.0, self.1 - other.1, self.2 - other.2)
+	}
+}
+
+/// Describe a line in 3-space by its start and end `Vector3` respectively
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Line3(pub Vector3, pub Vector3);
+
+impl Line3 {
+	/// Create a line using its end-point, starting in (0, 0, 0)
+	pub fn from_origin(p: Vector3) -> Line3 {
+		Line3(Vector3(0.0, 0.0, 0.0), p)
+	}
+
+	/// Create an iterator over every voxel the line passes through
+	///
+	/// This is the voxel traversal of Amanatides and Woo, the 3D counterpart of
+	/// `Line::supercover`. Voxel `(x, y, z)` spans from x inclusive to x+1
+	/// exclusive, and likewise for y and z. Only one coordinate changes per step,
+	/// so a line through an edge or corner also visits a voxel next to it.
+	///
+	/// ```
+	/// use tile_net::{Line3, Vector3};
+	/// let line = Line3(Vector3(0.5, 0.5, 0.5), Vector3(2.5, 0.5, 1.5));
+	/// let voxels: Vec<_> = line.supercover().collect();
+	/// assert_eq!(voxels.first(), Some(&(0, 0, 0)));
+	/// assert_eq!(voxels.last(), Some(&(2, 0, 1)));
+	/// assert_eq!(voxels.len(), 4);
+	/// ```
+	pub fn supercover(&self) -> VoxelCover {
+		let (start, stop) = (self.0, self.1);
+		let direction = stop - start;
+		let start = [start.0, start.1, start.2];
+		let stop = [stop.0, stop.1, stop.2];
+		let direction = [direction.0, direction.1, direction.2];
+		let mut cover = VoxelCover {
+			current: [0; 3],
+			destination: [0; 3],
+			step: [0; 3],
+			t_max: [0.0; 3],
+			t_delta: [0.0; 3],
+			done: false,
+		};
+		for axis in 0..3 {
+			cover.current[axis] = start[axis].floor() as i32;
+			cover.destination[axis] = stop[axis].floor() as i32;
+			cover.step[axis] = (cover.destination[axis] - cover.current[axis]).signum();
+			let fraction = start[axis] - start[axis].floor();
+			let length = direction[axis].abs();
+			cover.t_delta[axis] = 1.0 / length;
+			cover.t_max[axis] = if direction[axis] < 0.0 {
+				fraction / length
+			} else {
+				(1.0 - fraction) / length
+			};
+		}
+		cover
+	}
+}
+
+/// Iterator over the voxels along a line
+///
+/// Created by `Line3::supercover`. The first voxel contains the start of the
+/// line and the last voxel contains its end.
+#[derive(Clone)]
+pub struct VoxelCover {
+	current: [i32; 3],
+	destination: [i32; 3],
+	step: [i32; 3],
+	t_max: [f32; 3],
+	t_delta: [f32; 3],
+	done: bool,
+}
+
+impl Iterator for VoxelCover {
+	type Item = (i32, i32, i32);
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+		let old = (self.current[0], self.current[1], self.current[2]);
+		// Only axes that have not reached the destination may step, so rounding
+		// can never carry the traversal past the end of the line
+		let axis = (0..3)
+			.filter(|x| self.current[*x] != self.destination[*x])
+			.min_by(|a, b| self.t_max[*a].partial_cmp(&self.t_max[*b]).unwrap());
+		match axis {
+			Some(axis) => {
+				self.current[axis] += self.step[axis];
+				self.t_max[axis] += self.t_delta[axis];
+			}
+			None => self.done = true,
+		}
+		Some(old)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn cover(to: (f32, f32, f32)) -> Vec<(i32, i32, i32)> {
+		Line3::from_origin(Vector3::from_tuple(to)).supercover().collect()
+	}
+
+	#[test]
+	fn axis_lines() {
+		assert_eq!(cover((0.0, 0.0, 0.5)), vec![(0, 0, 0)]);
+		assert_eq!(cover((0.0, 0.0, 3.0)), (0..4).map(|z| (0, 0, z)).collect::<Vec<_>>());
+		assert_eq!(cover((-2.0, 0.0, 0.0)), (0..3).map(|x| (-x, 0, 0)).collect::<Vec<_>>());
+		assert_eq!(cover((0.0, -0.1, 0.0)), vec![(0, 0, 0), (0, -1, 0)]);
+	}
+
+	#[test]
+	fn matches_two_dimensions() {
+		use super::super::super::{Line, Vector};
+		let lines =
+			[((0.3, 0.2), (5.7, 2.9)), ((4.5, 0.5), (-3.2, 7.1)), ((2.2, 9.9), (2.8, -4.1))];
+		for &(from, to) in &lines {
+			let flat = Line(Vector::from_tuple(from), Vector::from_tuple(to)).supercover();
+			let voxels = Line3(Vector3(from.0, from.1, 0.5), Vector3(to.0, to.1, 0.5)).supercover();
+			assert!(flat.map(|x| (x.0, x.1, 0)).eq(voxels));
+		}
+	}
+
+	#[test]
+	fn diagonal_is_connected() {
+		let voxels = Line3(Vector3(0.1, 0.2, 0.3), Vector3(7.9, -5.3, 4.4)).supercover();
+		let voxels: Vec<_> = voxels.collect();
+		assert_eq!(voxels.len(), 1 + 7 + 6 + 4);
+		for pair in voxels.windows(2) {
+			let difference = (pair[1].0 - pair[0].0).abs() + (pair[1].1 - pair[0].1).abs() +
+			                 (pair[1].2 - pair[0].2).abs();
+			assert_eq!(difference, 1);
+		}
+		assert_eq!(voxels.last(), Some(&(7, -6, 4)));
+	}
+}
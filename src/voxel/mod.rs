@@ -0,0 +1,194 @@
+use std::cell::RefCell;
+pub use self::collable::{VoxelCollable, VoxelPoints, VoxelReport, VoxelState};
+pub use self::cover::{Line3, Vector3, VoxelCover};
+
+mod collable;
+mod cover;
+
+/// Coordinate of a voxel
+type Voxel = (i32, i32, i32);
+
+/// A 3D grid of voxels, the counterpart of `TileNet`
+///
+/// Voxel `(x, y, z)` spans from x inclusive to x+1 exclusive, and likewise for y
+/// and z. The voxels are stored by x first, then y, then z.
+///
+/// ```
+/// use tile_net::*;
+/// let mut net: VoxelNet<usize> = VoxelNet::new(4, 4, 4);
+/// net.set_box(&1, (0, 3, 0), (3, 3, 3));
+/// assert_eq!(net.get((2, 3, 1)), Some(&1));
+/// assert_eq!(net.get((2, 2, 1)), Some(&0));
+/// assert_eq!(net.get((4, 0, 0)), None);
+/// ```
+#[derive(Clone, Debug)]
+pub struct VoxelNet<T> {
+	map: Vec<T>,
+	size: (usize, usize, usize),
+}
+
+impl<T> VoxelNet<T>
+	where T: Clone + Default
+{
+	/// Create a new voxel net of the size (x, y, z)
+	///
+	/// The voxels will be Default-created
+	pub fn new(x: usize, y: usize, z: usize) -> VoxelNet<T> {
+		VoxelNet {
+			map: vec![T::default(); x * y * z],
+			size: (x, y, z),
+		}
+	}
+}
+
+impl<T> VoxelNet<T>
+	where T: Clone
+{
+	/// Set a box in the voxel net, including both corners
+	pub fn set_box(&mut self,
+	               value: &T,
+	               start: (usize, usize, usize),
+	               stop: (usize, usize, usize)) {
+		for z in start.2..stop.2 + 1 {
+			for y in start.1..stop.1 + 1 {
+				for x in start.0..stop.0 + 1 {
+					self.set(value, (x, y, z));
+				}
+			}
+		}
+	}
+
+	/// Set a single voxel
+	pub fn set(&mut self, value: &T, p: (usize, usize, usize)) {
+		if let Some(old) = self.get_mut(p) {
+			*old = value.clone();
+		}
+	}
+}
+
+impl<T> VoxelNet<T> {
+	/// Get the raw array of voxels
+	pub fn get_raw(&self) -> &[T] {
+		&self.map
+	}
+
+	/// Get the raw array of voxels, mutably
+	pub fn get_raw_mut(&mut self) -> &mut [T] {
+		&mut self.map
+	}
+
+	/// Get the size as (x, y, z)
+	pub fn get_size(&self) -> (usize, usize, usize) {
+		self.size
+	}
+
+	/// Get a voxel, or `None` if it lies outside the net
+	pub fn get(&self, p: (usize, usize, usize)) -> Option<&T> {
+		self.index(p).map(|x| &self.map[x])
+	}
+
+	/// Get a voxel mutably, or `None` if it lies outside the net
+	pub fn get_mut(&mut self, p: (usize, usize, usize)) -> Option<&mut T> {
+		self.index(p).map(move |x| &mut self.map[x])
+	}
+
+	/// Create an iterator of voxels from an iterator over indices
+	pub fn collide_set<I>(&self, list: I) -> VoxelSet<'_, T, I>
+		where I: Iterator<Item = (i32, i32, i32)>
+	{
+		VoxelSet {
+			voxelnet: self,
+			points: list,
+			last_coord: (0, 0, 0),
+			last_voxel: None,
+			blocking: None,
+		}
+	}
+
+	fn index(&self, p: (usize, usize, usize)) -> Option<usize> {
+		let (x, y, z) = self.size;
+		if p.0 < x && p.1 < y && p.2 < z {
+			Some(p.0 + (p.1 + p.2 * y) * x)
+		} else {
+			None
+		}
+	}
+}
+
+/// Iterator over the voxels at a list of indices
+///
+/// Created by `VoxelNet::collide_set`. Indices outside the net are skipped.
+pub struct VoxelSet<'a, T, I>
+	where T: 'a
+{
+	voxelnet: &'a VoxelNet<T>,
+	points: I,
+	last_coord: (i32, i32, i32),
+	last_voxel: Option<(i32, i32, i32)>,
+	blocking: Option<&'a RefCell<Vec<Voxel>>>,
+}
+
+impl<'a, T, I> VoxelSet<'a, T, I>
+	where T: 'a,
+	      I: Iterator<Item = (i32, i32, i32)>
+{
+	/// Get the coordinate of the last voxel
+	pub fn get_coords(&self) -> (i32, i32, i32) {
+		self.last_coord
+	}
+
+	/// Mark the voxel that was returned last as blocking the movement
+	///
+	/// Works like `TileSet::block`; the marked voxels are listed in `VoxelReport::blocking`.
+	pub fn block(&self) {
+		if let (Some(voxel), Some(blocking)) = (self.last_voxel, self.blocking) {
+			let mut blocking = blocking.borrow_mut();
+			if !blocking.contains(&voxel) {
+				blocking.push(voxel);
+			}
+		}
+	}
+
+	/// Record the voxels passed to `block` in a list
+	pub(crate) fn recording(mut self, blocking: &'a RefCell<Vec<Voxel>>) -> Self {
+		self.blocking = Some(blocking);
+		self
+	}
+}
+
+impl<'a, T, I> Iterator for VoxelSet<'a, T, I>
+	where T: 'a,
+	      I: Iterator<Item = (i32, i32, i32)>
+{
+	type Item = &'a T;
+	fn next(&mut self) -> Option<Self::Item> {
+		for point in &mut self.points {
+			self.last_coord = point;
+			if point.0 >= 0 && point.1 >= 0 && point.2 >= 0 {
+				let index = (point.0 as usize, point.1 as usize, point.2 as usize);
+				let voxel = self.voxelnet.get(index);
+				if voxel.is_some() {
+					self.last_voxel = Some(point);
+					return voxel;
+				}
+			}
+		}
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn collide_set_skips_outside() {
+		let mut net: VoxelNet<usize> = VoxelNet::new(3, 3, 3);
+		net.set(&5, (2, 1, 0));
+		let line = Line3(Vector3(-1.5, 1.5, 0.5), Vector3(4.5, 1.5, 0.5));
+		let mut set = net.collide_set(line.supercover());
+		assert_eq!(set.by_ref().cloned().collect::<Vec<_>>(), vec![0, 0, 5]);
+		assert_eq!(set.get_coords(), (4, 1, 0));
+		assert_eq!(net.get_raw().iter().filter(|x| **x == 5).count(), 1);
+	}
+}